@@ -0,0 +1,208 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// User settings read from `$XDG_CONFIG_HOME/slingshot/config`. The file holds one
+/// `key = value` pair per line; lines starting with `#` are comments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub show_hidden: bool,
+    pub ignore: Vec<String>,
+    pub max_depth: usize,
+    pub index_roots: Vec<String>,
+    pub remember_filter: bool,
+    pub persist_dir_state: bool,
+    pub restore_session: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            show_hidden: false,
+            ignore: vec![
+                ".git".to_owned(),
+                "target".to_owned(),
+                "node_modules".to_owned(),
+            ],
+            max_depth: 12,
+            index_roots: env::var("HOME").map(|home| vec![home]).unwrap_or_default(),
+            remember_filter: false,
+            persist_dir_state: false,
+            restore_session: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Config {
+        match config_dir().map(|dir| fs::read_to_string(dir.join("config"))) {
+            Some(Ok(contents)) => Config::parse(&contents),
+            _ => Config::default(),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Config {
+        let mut config = Config::default();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+
+            match key {
+                "show_hidden" => config.show_hidden = value == "true",
+                "remember_filter" => config.remember_filter = value == "true",
+                "persist_dir_state" => config.persist_dir_state = value == "true",
+                "restore_session" => config.restore_session = value == "true",
+                "ignore" => config.ignore = parse_list(value),
+                "index_roots" => {
+                    config.index_roots = parse_list(value).iter().map(|r| expand_home(r)).collect()
+                }
+                "max_depth" => {
+                    if let Ok(depth) = value.parse() {
+                        config.max_depth = depth;
+                    }
+                }
+                _ => {}
+            }
+        }
+        config
+    }
+
+    /// Whether a directory entry called `name` should be skipped by recursive searches.
+    pub fn is_ignored(&self, name: &str) -> bool {
+        (!self.show_hidden && name.starts_with('.')) || self.ignore.iter().any(|i| i == name)
+    }
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_owned())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Replaces a leading `~` with the home directory.
+pub fn expand_home(path: &str) -> String {
+    match (path.strip_prefix('~'), env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}{}", home, rest),
+        _ => path.to_owned(),
+    }
+}
+
+/// Replaces the home directory at the start of `path` with `~`.
+pub fn abbreviate_home(path: &str) -> String {
+    match env::var("HOME") {
+        Ok(home) if !home.is_empty() => match path.strip_prefix(&home) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("~{}", rest),
+            _ => path.to_owned(),
+        },
+        _ => path.to_owned(),
+    }
+}
+
+/// The command that opens files: `$VISUAL`, then `$EDITOR`, then `nvim`. The value is split on
+/// whitespace, so it can carry arguments, as in `code -w`.
+pub fn editor() -> Vec<String> {
+    editor_from(env::var("VISUAL").ok(), env::var("EDITOR").ok())
+}
+
+fn editor_from(visual: Option<String>, editor: Option<String>) -> Vec<String> {
+    [visual, editor]
+        .into_iter()
+        .flatten()
+        .map(|value| {
+            value
+                .split_whitespace()
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        })
+        .find(|words| !words.is_empty())
+        .unwrap_or_else(|| vec!["nvim".to_owned()])
+}
+
+fn xdg_dir(variable: &str, fallback: &str) -> Option<PathBuf> {
+    match env::var(variable) {
+        Ok(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => env::var("HOME")
+            .ok()
+            .map(|home| PathBuf::from(home).join(fallback)),
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config").map(|dir| dir.join("slingshot"))
+}
+
+/// Where slingshot keeps its own files, `$XDG_DATA_HOME/slingshot`.
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share").map(|dir| dir.join("slingshot"))
+}
+
+/// The home trash of the freedesktop Trash specification, `$XDG_DATA_HOME/Trash`.
+pub fn trash_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share").map(|dir| dir.join("Trash"))
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(
+            "# slingshot settings\n\
+             show_hidden = true\n\
+             ignore = target, .cache ,\n\
+             max_depth = 3\n\
+             index_roots = /srv, ~/code\n\
+             remember_filter = true\n\
+             unknown = value\n",
+        );
+
+        assert_eq!(
+            config,
+            Config {
+                show_hidden: true,
+                ignore: vec!["target".to_owned(), ".cache".to_owned()],
+                max_depth: 3,
+                index_roots: vec!["/srv".to_owned(), expand_home("~/code")],
+                remember_filter: true,
+                persist_dir_state: false,
+                restore_session: false,
+            }
+        );
+        assert_eq!(Config::parse("max_depth = deep"), Config::default());
+    }
+
+    #[test]
+    fn test_is_ignored() {
+        let config = Config::default();
+        assert!(config.is_ignored(".env"));
+        assert!(config.is_ignored("node_modules"));
+        assert!(!config.is_ignored("src"));
+
+        let config = Config::parse("show_hidden = true");
+        assert!(!config.is_ignored(".env"));
+        assert!(config.is_ignored(".git"));
+    }
+
+    #[test]
+    fn test_editor() {
+        assert_eq!(editor_from(None, None), vec!["nvim"]);
+        assert_eq!(
+            editor_from(Some("code -w".to_owned()), Some("vi".to_owned())),
+            vec!["code", "-w"]
+        );
+        assert_eq!(
+            editor_from(Some(" ".to_owned()), Some("vi".to_owned())),
+            vec!["vi"]
+        );
+    }
+}
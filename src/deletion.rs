@@ -0,0 +1,242 @@
+use crate::config;
+use crate::layout;
+use std::fs;
+use std::path::Path;
+
+/// Permanent deletions above this many bytes have to be confirmed by typing.
+const LARGE_DELETION: u64 = 100 * 1024 * 1024;
+
+/// What typing out confirms a recursive or large permanent deletion.
+pub const CONFIRMATION: &str = "delete";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    File,
+    Dir,
+    Symlink,
+    Missing,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pending {
+    pub path: String,
+    pub kind: Kind,
+    /// For directories, the total size of everything below them.
+    pub size: u64,
+    /// Number of entries below a directory.
+    pub contents: usize,
+}
+
+/// Every entry about to be trashed or deleted, with the totals shown before confirming.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Review {
+    pub entries: Vec<Pending>,
+    pub permanent: bool,
+    pub files: usize,
+    pub dirs: usize,
+    pub bytes: u64,
+}
+
+impl Kind {
+    fn name(&self) -> &'static str {
+        match self {
+            Kind::File => "file",
+            Kind::Dir => "dir",
+            Kind::Symlink => "link",
+            Kind::Missing => "missing",
+        }
+    }
+}
+
+/// Files, directories and bytes below `dir`. Symlinks count as files and are never followed,
+/// and unreadable directories count as empty.
+fn measure(dir: &Path) -> (usize, usize, u64) {
+    let (mut files, mut dirs, mut bytes) = (0, 0, 0);
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return (0, 0, 0),
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        match entry.path().symlink_metadata() {
+            Ok(metadata) if metadata.is_dir() => {
+                let (f, d, b) = measure(&entry.path());
+                files += f;
+                dirs += d + 1;
+                bytes += b;
+            }
+            Ok(metadata) => {
+                files += 1;
+                bytes += metadata.len();
+            }
+            Err(_) => {}
+        }
+    }
+    (files, dirs, bytes)
+}
+
+/// Deletes `path` and everything below it without following symlinks. Entries that cannot be
+/// deleted are skipped and returned with their error, so one failure does not stop the rest.
+pub fn remove_all(path: &Path) -> Vec<(String, String)> {
+    let failure = |e: std::io::Error| vec![(path.to_string_lossy().into_owned(), e.to_string())];
+
+    let metadata = match path.symlink_metadata() {
+        Ok(metadata) => metadata,
+        Err(e) => return failure(e),
+    };
+    if !metadata.is_dir() {
+        return fs::remove_file(path).err().map(failure).unwrap_or_default();
+    }
+
+    let mut failures = Vec::new();
+    match fs::read_dir(path) {
+        Ok(entries) => {
+            for entry in entries {
+                match entry {
+                    Ok(entry) => failures.extend(remove_all(&entry.path())),
+                    Err(e) => failures.extend(failure(e)),
+                }
+            }
+        }
+        Err(e) => return failure(e),
+    }
+
+    // A directory that still has entries left cannot go, and saying so again adds nothing.
+    if failures.is_empty() {
+        if let Err(e) = fs::remove_dir(path) {
+            failures.extend(failure(e));
+        }
+    }
+    failures
+}
+
+impl Review {
+    /// Looks up the type and size of every path, counting everything below directories.
+    /// Symlinks are reported as links rather than as what they point to.
+    pub fn new(paths: &[String], permanent: bool) -> Review {
+        let mut review = Review {
+            permanent,
+            ..Review::default()
+        };
+        for path in paths {
+            let (kind, size, contents) = match fs::symlink_metadata(path) {
+                Ok(metadata) if metadata.is_dir() => {
+                    let (files, dirs, bytes) = measure(Path::new(path));
+                    review.files += files;
+                    review.dirs += dirs;
+                    (Kind::Dir, bytes, files + dirs)
+                }
+                Ok(metadata) if metadata.file_type().is_symlink() => (Kind::Symlink, 0, 0),
+                Ok(metadata) => (Kind::File, metadata.len(), 0),
+                Err(_) => (Kind::Missing, 0, 0),
+            };
+            match kind {
+                Kind::Dir => review.dirs += 1,
+                Kind::Missing => {}
+                _ => review.files += 1,
+            }
+            review.bytes += size;
+            review.entries.push(Pending {
+                path: path.to_owned(),
+                kind,
+                size,
+                contents,
+            });
+        }
+        review
+    }
+
+    /// Trashing can be undone, but permanently deleting directories or large amounts of data
+    /// is not confirmed with a single key.
+    pub fn needs_typed_confirmation(&self) -> bool {
+        let recursive = self.entries.iter().any(|entry| entry.kind == Kind::Dir);
+        self.permanent && (recursive || self.bytes > LARGE_DELETION)
+    }
+
+    /// One line per entry (type, size, path and what a directory contains) followed by the
+    /// totals.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let size = match entry.kind {
+                    Kind::File | Kind::Dir => layout::format_size(entry.size),
+                    _ => "".to_owned(),
+                };
+                let mut line = format!(
+                    "{:<8}{:>8}  {}",
+                    entry.kind.name(),
+                    size,
+                    config::abbreviate_home(&entry.path)
+                );
+                if entry.kind == Kind::Dir {
+                    line.push_str(&format!("  ({} entries)", entry.contents));
+                }
+                line
+            })
+            .collect();
+        lines.push("".to_owned());
+        lines.push(format!(
+            "{} files, {} directories, {}",
+            self.files,
+            self.dirs,
+            layout::format_size(self.bytes)
+        ));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod deletion_tests {
+    use super::*;
+
+    #[test]
+    fn test_review() {
+        let dir = std::env::temp_dir().join(format!("slingshot_deletion_{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("file.txt"), "12345").unwrap();
+
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_owned();
+        let review = Review::new(&[path("file.txt"), path("missing")], true);
+        assert_eq!(review.files, 1);
+        assert_eq!(review.bytes, 5);
+        assert_eq!(review.entries[1].kind, Kind::Missing);
+        assert!(!review.needs_typed_confirmation());
+        assert_eq!(review.lines().last().unwrap(), "1 files, 0 directories, 5B");
+
+        fs::write(dir.join("sub/a.txt"), "abc").unwrap();
+        fs::create_dir_all(dir.join("sub/nested")).unwrap();
+        let review = Review::new(&[path("sub")], true);
+        assert_eq!((review.files, review.dirs, review.bytes), (1, 2, 3));
+        assert!(review.lines()[0].ends_with("(2 entries)"));
+        assert!(review.needs_typed_confirmation());
+        assert!(!Review::new(&[path("sub")], false).needs_typed_confirmation());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_remove_all() {
+        let root = std::env::temp_dir().join(format!("slingshot_remove_{}", std::process::id()));
+        let outside = root.join("outside");
+        let tree = root.join("tree");
+        fs::create_dir_all(&outside).unwrap();
+        fs::create_dir_all(tree.join("a/b")).unwrap();
+        fs::write(outside.join("keep.txt"), "").unwrap();
+        fs::write(tree.join("a/b/file.txt"), "").unwrap();
+        std::os::unix::fs::symlink(&outside, tree.join("a/link")).unwrap();
+
+        let review = Review::new(&[tree.to_str().unwrap().to_owned()], true);
+        assert_eq!((review.files, review.dirs), (2, 3));
+
+        assert!(remove_all(&tree).is_empty());
+        assert!(!tree.exists());
+        assert!(outside.join("keep.txt").exists());
+
+        let failures = remove_all(&root.join("missing"));
+        assert_eq!(failures.len(), 1);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,168 @@
+use crate::config;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const MAX_ENTRIES: usize = 1000;
+
+/// How a directory looked when the user left it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DirState {
+    pub selected: Option<String>,
+    pub scroll_offset: usize,
+    pub filter: String,
+    last_used: u64,
+}
+
+impl DirState {
+    pub fn new(selected: Option<String>, scroll_offset: usize, filter: &str) -> DirState {
+        DirState {
+            selected,
+            scroll_offset,
+            filter: filter.to_owned(),
+            last_used: 0,
+        }
+    }
+}
+
+/// The last state of every visited directory, so re-entering one restores its selection.
+/// With a path set, the cache is stored one `dir\tscroll_offset\tselected\tfilter` line per
+/// directory in `$XDG_DATA_HOME/slingshot/dirstate`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DirStates {
+    pub path: Option<PathBuf>,
+    entries: HashMap<String, DirState>,
+    clock: u64,
+}
+
+pub fn dir_states_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("dirstate"))
+}
+
+impl DirStates {
+    pub fn load(path: &Path) -> DirStates {
+        let mut states = DirStates {
+            path: Some(path.to_owned()),
+            ..DirStates::default()
+        };
+
+        let contents = fs::read_to_string(path).unwrap_or_default();
+        for line in contents.lines() {
+            let fields: Vec<&str> = line.splitn(4, '\t').collect();
+            if let [dir, scroll_offset, selected, filter] = fields[..] {
+                let selected = Some(selected.to_owned()).filter(|name| !name.is_empty());
+                let scroll_offset = scroll_offset.parse().unwrap_or(0);
+                states.remember(dir, DirState::new(selected, scroll_offset, filter));
+            }
+        }
+        states
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut entries: Vec<(&String, &DirState)> = self.entries.iter().collect();
+        entries.sort_by_key(|(_, state)| state.last_used);
+        let contents: String = entries
+            .into_iter()
+            .map(|(dir, state)| {
+                format!(
+                    "{}\t{}\t{}\t{}\n",
+                    dir,
+                    state.scroll_offset,
+                    state.selected.as_deref().unwrap_or(""),
+                    state.filter
+                )
+            })
+            .collect();
+
+        let tmp = path.with_extension("tmp");
+        fs::File::create(&tmp)?.write_all(contents.as_bytes())?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, dir: &str) -> Option<&DirState> {
+        self.entries.get(dir)
+    }
+
+    /// Stores the state of `dir`, forgetting the least recently used directory once the cache
+    /// is full.
+    pub fn remember(&mut self, dir: &str, mut state: DirState) {
+        self.clock += 1;
+        state.last_used = self.clock;
+        self.entries.insert(dir.to_owned(), state);
+
+        if self.entries.len() > MAX_ENTRIES {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, state)| state.last_used)
+                .map(|(dir, _)| dir.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Like `remember`, but also writes the cache to disk when it has a path.
+    pub fn remember_and_save(&mut self, dir: &str, state: DirState) -> Result<(), Box<dyn Error>> {
+        self.remember(dir, state);
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod dirstate_tests {
+    use super::*;
+
+    #[test]
+    fn test_remember_and_persist() {
+        let path = std::env::temp_dir().join(format!("slingshot_dirstate_{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut states = DirStates::load(&path);
+        let src = DirState::new(Some("main.rs".to_owned()), 3, "ma");
+        states.remember_and_save("/code/src", src.clone()).unwrap();
+        states
+            .remember_and_save("/code", DirState::new(None, 0, ""))
+            .unwrap();
+
+        let reloaded = DirStates::load(&path);
+        assert_eq!(
+            reloaded.get("/code/src").map(|s| &s.selected),
+            Some(&src.selected)
+        );
+        assert_eq!(reloaded.get("/code/src").map(|s| s.scroll_offset), Some(3));
+        assert_eq!(
+            reloaded.get("/code/src").map(|s| s.filter.as_str()),
+            Some("ma")
+        );
+        assert_eq!(reloaded.get("/code").map(|s| &s.selected), Some(&None));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut states = DirStates::default();
+        for i in 0..=MAX_ENTRIES {
+            states.remember(&format!("/dir{}", i), DirState::default());
+        }
+        states.remember("/dir0", DirState::default());
+        states.remember("/new", DirState::default());
+
+        assert!(states.get("/dir0").is_some());
+        assert!(states.get("/dir1").is_none());
+        assert!(states.get("/dir2").is_none());
+        assert!(states.get("/new").is_some());
+    }
+}
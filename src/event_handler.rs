@@ -1,16 +1,53 @@
-use crate::state_handler::{AppState, KeybindMode};
+use crate::marks;
+use crate::register::Conflict;
+use crate::state_handler::{AppMode, AppState, KeybindMode};
 use crossterm;
 use crossterm::event::{KeyCode, KeyModifiers};
 use std::error::Error;
 
 pub fn handle_key(key_code: KeyCode, app_state: &mut AppState) -> Result<(), Box<dyn Error>> {
-    match app_state.keybind_mode {
-        KeybindMode::Normal => handle_normal_mode(key_code, app_state),
-        KeybindMode::Insert => handle_key_code_insert(key_code, app_state),
+    match app_state.app_mode {
+        AppMode::Find | AppMode::IndexSearch | AppMode::Jump => {
+            handle_find_mode(key_code, app_state)
+        }
+        AppMode::Grep => handle_grep_mode(key_code, app_state),
+        AppMode::Bookmarks => handle_bookmarks_mode(key_code, app_state),
+        AppMode::Search => handle_search_mode(key_code, app_state),
+        AppMode::Trash if app_state.keybind_mode != KeybindMode::Dialog => {
+            handle_trash_mode(key_code, app_state)
+        }
+        AppMode::History => handle_history_mode(key_code, app_state),
+        _ => match app_state.keybind_mode {
+            KeybindMode::Normal => handle_normal_mode(key_code, app_state),
+            KeybindMode::Insert => handle_key_code_insert(key_code, app_state),
+            KeybindMode::Visual => handle_visual_mode(key_code, app_state),
+            KeybindMode::Dialog => handle_dialog_mode(key_code, app_state),
+            KeybindMode::Rename => handle_rename_mode(key_code, app_state),
+            KeybindMode::Conflict => handle_conflict_mode(key_code, app_state),
+        },
     }
 }
 
 fn handle_normal_mode(key_code: KeyCode, app_state: &mut AppState) -> Result<(), Box<dyn Error>> {
+    if let Some(pending) = app_state.pending_key.take() {
+        let count = app_state.take_count();
+        return handle_pending_key(pending, count, key_code, app_state);
+    }
+
+    // Counts and the first key of two key commands wait for the next key.
+    match key_code {
+        KeyCode::Char(c) if c.is_ascii_digit() && (c != '0' || app_state.count.is_some()) => {
+            app_state.push_count_digit(c.to_digit(10).unwrap_or(0));
+            return Ok(());
+        }
+        KeyCode::Char(c @ ('m' | '\'' | 'g' | 'S' | 'c' | 'd' | 'y')) => {
+            app_state.pending_key = Some(c);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let count = app_state.take_count();
     match key_code {
         KeyCode::Char('i') => {
             app_state.keybind_mode = KeybindMode::Insert;
@@ -20,32 +57,152 @@ fn handle_normal_mode(key_code: KeyCode, app_state: &mut AppState) -> Result<(),
             app_state.keybind_mode = KeybindMode::Insert;
             return Ok(());
         }
+        KeyCode::Char('r') => {
+            app_state.start_rename(false);
+            return Ok(());
+        }
+        KeyCode::Char('R') => {
+            app_state.start_bulk_rename();
+            return Ok(());
+        }
         KeyCode::Char('h') => {
-            app_state.handle_move_back();
+            if app_state.tree.enabled {
+                app_state.handle_tree_collapse();
+            } else {
+                app_state.handle_move_back();
+            }
             return Ok(());
         }
         KeyCode::Char('j') => {
-            app_state.update_selected_index(KeyCode::Down);
+            app_state.move_selection(KeyCode::Down, count);
             return Ok(());
         }
         KeyCode::Char('k') => {
-            app_state.update_selected_index(KeyCode::Up);
+            app_state.move_selection(KeyCode::Up, count);
+            return Ok(());
+        }
+        KeyCode::Char('G') => {
+            app_state.handle_goto(count, true);
+            return Ok(());
+        }
+        KeyCode::Char(c @ ('H' | 'M' | 'L')) => {
+            app_state.handle_screen_position(c, count);
+            return Ok(());
+        }
+        KeyCode::Char('}') => {
+            app_state.handle_group_jump(true, count);
+            return Ok(());
+        }
+        KeyCode::Char('{') => {
+            app_state.handle_group_jump(false, count);
             return Ok(());
         }
         KeyCode::Char('l') => {
-            app_state.handle_enter();
+            if app_state.tree.enabled {
+                app_state.handle_tree_expand();
+            } else {
+                app_state.handle_enter();
+            }
             return Ok(());
         }
-        KeyCode::Char('d') => {
-            app_state.handle_mark_delete();
+        KeyCode::Char('t') => {
+            app_state.toggle_tree_mode();
             return Ok(());
         }
-        KeyCode::Char('y') => {
-            app_state.handle_confirm_delete();
+        KeyCode::Char('f') => {
+            app_state.start_find_mode();
+            return Ok(());
+        }
+        KeyCode::Char('F') => {
+            app_state.start_index_mode();
+            return Ok(());
+        }
+        KeyCode::Char('z') => {
+            app_state.start_jump_mode();
+            return Ok(());
+        }
+        KeyCode::Char('B') => {
+            app_state.start_bookmarks_mode();
+            return Ok(());
+        }
+        KeyCode::Delete => {
+            app_state.handle_mark_delete(count.unwrap_or(1));
+            return Ok(());
+        }
+        KeyCode::Char('x') => {
+            app_state.handle_yank(count.unwrap_or(1), true);
+            return Ok(());
+        }
+        KeyCode::Char('p') => {
+            app_state.handle_paste();
+            return Ok(());
+        }
+        KeyCode::Char('D') => {
+            app_state.start_deletion_review(false);
+            return Ok(());
+        }
+        KeyCode::Char('X') => {
+            app_state.start_deletion_review(true);
+            return Ok(());
+        }
+        KeyCode::Char('T') => {
+            app_state.start_trash_mode();
+            return Ok(());
+        }
+        KeyCode::Char('u') => {
+            app_state.handle_undo();
+            return Ok(());
+        }
+        KeyCode::Char('U') => {
+            app_state.start_history_mode();
+            return Ok(());
+        }
+        KeyCode::Char(' ') => {
+            app_state.handle_toggle_selection(count.unwrap_or(1));
+            return Ok(());
+        }
+        KeyCode::Char('v') => {
+            app_state.start_visual_mode(false);
+            return Ok(());
+        }
+        KeyCode::Char('V') => {
+            app_state.start_visual_mode(true);
+            return Ok(());
+        }
+        KeyCode::Char('/') => {
+            app_state.start_search(true);
+            return Ok(());
+        }
+        KeyCode::Char('?') => {
+            app_state.start_search(false);
             return Ok(());
         }
         KeyCode::Char('n') => {
-            app_state.toggle_command_mode();
+            app_state.repeat_search(false, count);
+            return Ok(());
+        }
+        KeyCode::Char('N') => {
+            app_state.repeat_search(true, count);
+            return Ok(());
+        }
+        KeyCode::Char('*') => {
+            app_state.handle_same_extension(count);
+            return Ok(());
+        }
+        KeyCode::Esc => {
+            app_state.clear_search_highlight();
+            return Ok(());
+        }
+        KeyCode::Char('s') => {
+            app_state.toggle_view_mode();
+            return Ok(());
+        }
+        KeyCode::Char('b') => {
+            app_state.toggle_hex_preview();
+            return Ok(());
+        }
+        KeyCode::Tab => {
+            app_state.handle_jump_forward();
             return Ok(());
         }
         KeyCode::Enter => {
@@ -59,6 +216,226 @@ fn handle_normal_mode(key_code: KeyCode, app_state: &mut AppState) -> Result<(),
     }
 }
 
+fn handle_visual_mode(key_code: KeyCode, app_state: &mut AppState) -> Result<(), Box<dyn Error>> {
+    if let KeyCode::Char(c) = key_code {
+        if c.is_ascii_digit() && (c != '0' || app_state.count.is_some()) {
+            app_state.push_count_digit(c.to_digit(10).unwrap_or(0));
+            return Ok(());
+        }
+    }
+
+    let count = app_state.take_count();
+    match key_code {
+        KeyCode::Char('j') | KeyCode::Down => {
+            app_state.move_selection(KeyCode::Down, Some(count.unwrap_or(1)));
+            return Ok(());
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            app_state.move_selection(KeyCode::Up, Some(count.unwrap_or(1)));
+            return Ok(());
+        }
+        KeyCode::Char('G') => {
+            app_state.handle_goto(count, true);
+            return Ok(());
+        }
+        KeyCode::Char('}') => {
+            app_state.handle_group_jump(true, count);
+            return Ok(());
+        }
+        KeyCode::Char('{') => {
+            app_state.handle_group_jump(false, count);
+            return Ok(());
+        }
+        KeyCode::Char('V') => {
+            app_state.extend_visual_to_groups();
+            return Ok(());
+        }
+        KeyCode::Char('d') | KeyCode::Delete => {
+            app_state.handle_visual_delete();
+            return Ok(());
+        }
+        KeyCode::Char('y') => {
+            app_state.handle_visual_yank(false);
+            return Ok(());
+        }
+        KeyCode::Char('x') => {
+            app_state.handle_visual_yank(true);
+            return Ok(());
+        }
+        KeyCode::Char(':') => {
+            app_state.handle_visual_command();
+            return Ok(());
+        }
+        KeyCode::Char(' ') => {
+            app_state.handle_visual_select();
+            return Ok(());
+        }
+        KeyCode::Char('v') | KeyCode::Esc => {
+            app_state.exit_visual_mode();
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+}
+
+fn handle_dialog_mode(key_code: KeyCode, app_state: &mut AppState) -> Result<(), Box<dyn Error>> {
+    let typed = app_state.dialog.typed.is_some();
+    match key_code {
+        KeyCode::Enter => {
+            app_state.confirm_dialog();
+            return Ok(());
+        }
+        KeyCode::Esc => {
+            app_state.cancel_dialog();
+            return Ok(());
+        }
+        KeyCode::Backspace => {
+            app_state.dialog.input.pop();
+            return Ok(());
+        }
+        KeyCode::Char(c) if typed => {
+            app_state.dialog.input.push(c);
+            return Ok(());
+        }
+        KeyCode::Char('y') => {
+            app_state.confirm_dialog();
+            return Ok(());
+        }
+        KeyCode::Char('n') | KeyCode::Char('q') => {
+            app_state.cancel_dialog();
+            return Ok(());
+        }
+        KeyCode::Char('j') | KeyCode::Down => {
+            app_state.dialog.scroll(true);
+            return Ok(());
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            app_state.dialog.scroll(false);
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+}
+
+fn handle_rename_mode(key_code: KeyCode, app_state: &mut AppState) -> Result<(), Box<dyn Error>> {
+    let editor = &mut app_state.rename.editor;
+    match key_code {
+        KeyCode::Enter => {
+            app_state.confirm_rename();
+            return Ok(());
+        }
+        KeyCode::Esc => {
+            app_state.cancel_rename();
+            return Ok(());
+        }
+        KeyCode::Char(c) => {
+            editor.insert(c);
+            return Ok(());
+        }
+        KeyCode::Backspace => {
+            editor.backspace();
+            return Ok(());
+        }
+        KeyCode::Delete => {
+            editor.delete();
+            return Ok(());
+        }
+        KeyCode::Left => {
+            editor.left();
+            return Ok(());
+        }
+        KeyCode::Right => {
+            editor.right();
+            return Ok(());
+        }
+        KeyCode::Home => {
+            editor.home();
+            return Ok(());
+        }
+        KeyCode::End => {
+            editor.end();
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+}
+
+fn handle_conflict_mode(key_code: KeyCode, app_state: &mut AppState) -> Result<(), Box<dyn Error>> {
+    let conflict = match key_code {
+        KeyCode::Char('o' | 'O') => Conflict::Overwrite,
+        KeyCode::Char('s' | 'S') => Conflict::Skip,
+        KeyCode::Char('r' | 'R') => Conflict::Rename,
+        KeyCode::Esc | KeyCode::Char('q') => {
+            app_state.cancel_paste();
+            return Ok(());
+        }
+        _ => return Ok(()),
+    };
+    let for_all = matches!(key_code, KeyCode::Char(c) if c.is_ascii_uppercase());
+    app_state.resolve_conflict(conflict, for_all);
+    return Ok(());
+}
+
+/// Second key of a two key command such as `ma`, `'a`, `gg` `Sa`, `yy` or `cw`.
+fn handle_pending_key(
+    pending: char,
+    count: Option<usize>,
+    key_code: KeyCode,
+    app_state: &mut AppState,
+) -> Result<(), Box<dyn Error>> {
+    match (pending, key_code) {
+        ('g', KeyCode::Char('g')) => {
+            app_state.handle_goto(count, false);
+            return Ok(());
+        }
+        ('m', KeyCode::Char(c)) if marks::is_valid_name(c) => {
+            app_state.handle_set_mark(c);
+            return Ok(());
+        }
+        ('\'', KeyCode::Char('\'')) => {
+            app_state.handle_jump_previous();
+            return Ok(());
+        }
+        ('\'', KeyCode::Char(c)) if marks::is_valid_name(c) => {
+            app_state.handle_jump_to_mark(c);
+            return Ok(());
+        }
+        ('S', KeyCode::Char('a')) => {
+            app_state.handle_select_all();
+            return Ok(());
+        }
+        ('S', KeyCode::Char('i')) => {
+            app_state.handle_invert_selection();
+            return Ok(());
+        }
+        ('S', KeyCode::Char('c')) => {
+            app_state.handle_clear_selection();
+            return Ok(());
+        }
+        ('S', KeyCode::Char('/')) => {
+            app_state.start_select_pattern();
+            return Ok(());
+        }
+        ('y', KeyCode::Char('y')) => {
+            app_state.handle_yank(count.unwrap_or(1), false);
+            return Ok(());
+        }
+        ('d', KeyCode::Char('d')) => {
+            app_state.handle_yank(count.unwrap_or(1), true);
+            return Ok(());
+        }
+        ('c', KeyCode::Char('w')) => {
+            app_state.start_rename(true);
+            return Ok(());
+        }
+        (_, KeyCode::Esc) => return Ok(()),
+        _ => {
+            app_state.handle_unsupported_input();
+            return Ok(());
+        }
+    }
+}
+
 pub fn handle_key_code_insert(
     key_code: KeyCode,
     app_state: &mut AppState,
@@ -87,7 +464,7 @@ pub fn handle_key_code_insert(
         KeyCode::Esc => {
             if app_state.displayed_paths.len() == 0 {
                 app_state.user_input = "".to_owned();
-                app_state.displayed_paths = app_state.inner_paths.clone();
+                app_state.refresh_displayed_paths();
             }
             app_state.keybind_mode = KeybindMode::Normal;
             return Ok(());
@@ -96,6 +473,170 @@ pub fn handle_key_code_insert(
     }
 }
 
+fn handle_find_mode(key_code: KeyCode, app_state: &mut AppState) -> Result<(), Box<dyn Error>> {
+    match key_code {
+        KeyCode::Char(c) => {
+            app_state.handle_user_input_change(c);
+            return Ok(());
+        }
+        KeyCode::Backspace => {
+            app_state.handle_backspace();
+            return Ok(());
+        }
+        KeyCode::Enter => {
+            app_state.handle_enter();
+            return Ok(());
+        }
+        KeyCode::Up | KeyCode::Down => {
+            app_state.update_selected_index(key_code);
+            return Ok(());
+        }
+        KeyCode::Esc => {
+            app_state.exit_find_mode();
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+}
+
+fn handle_grep_mode(key_code: KeyCode, app_state: &mut AppState) -> Result<(), Box<dyn Error>> {
+    match key_code {
+        KeyCode::Char(c) => {
+            app_state.user_input.push(c);
+            return Ok(());
+        }
+        KeyCode::Backspace => {
+            app_state.user_input.pop();
+            return Ok(());
+        }
+        KeyCode::Enter => {
+            app_state.handle_enter();
+            return Ok(());
+        }
+        KeyCode::Up | KeyCode::Down => {
+            app_state.update_selected_index(key_code);
+            return Ok(());
+        }
+        KeyCode::Esc => {
+            app_state.exit_grep_mode();
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+}
+
+fn handle_search_mode(key_code: KeyCode, app_state: &mut AppState) -> Result<(), Box<dyn Error>> {
+    match key_code {
+        KeyCode::Char(c) => {
+            app_state.handle_search_input(c);
+            return Ok(());
+        }
+        KeyCode::Backspace => {
+            app_state.handle_search_backspace();
+            return Ok(());
+        }
+        KeyCode::Up | KeyCode::Down => {
+            app_state.handle_search_history(key_code == KeyCode::Up);
+            return Ok(());
+        }
+        KeyCode::Enter => {
+            app_state.confirm_search();
+            return Ok(());
+        }
+        KeyCode::Esc => {
+            app_state.cancel_search();
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+}
+
+fn handle_bookmarks_mode(
+    key_code: KeyCode,
+    app_state: &mut AppState,
+) -> Result<(), Box<dyn Error>> {
+    match key_code {
+        KeyCode::Char('j') | KeyCode::Down => {
+            app_state.update_selected_index(KeyCode::Down);
+            return Ok(());
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            app_state.update_selected_index(KeyCode::Up);
+            return Ok(());
+        }
+        KeyCode::Char('l') | KeyCode::Enter => {
+            app_state.handle_enter();
+            return Ok(());
+        }
+        KeyCode::Char('d') => {
+            app_state.handle_delete_bookmark();
+            return Ok(());
+        }
+        KeyCode::Char('c') => {
+            app_state.handle_remove_missing_bookmarks();
+            return Ok(());
+        }
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app_state.exit_find_mode();
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+}
+
+fn handle_trash_mode(key_code: KeyCode, app_state: &mut AppState) -> Result<(), Box<dyn Error>> {
+    match key_code {
+        KeyCode::Char('j') | KeyCode::Down => {
+            app_state.update_selected_index(KeyCode::Down);
+            return Ok(());
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            app_state.update_selected_index(KeyCode::Up);
+            return Ok(());
+        }
+        KeyCode::Char('r') | KeyCode::Enter => {
+            app_state.handle_enter();
+            return Ok(());
+        }
+        KeyCode::Char('d') => {
+            app_state.start_purge_trashed();
+            return Ok(());
+        }
+        KeyCode::Char('E') => {
+            app_state.start_empty_trash();
+            return Ok(());
+        }
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app_state.exit_trash_mode();
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+}
+
+fn handle_history_mode(key_code: KeyCode, app_state: &mut AppState) -> Result<(), Box<dyn Error>> {
+    match key_code {
+        KeyCode::Char('j') | KeyCode::Down => {
+            app_state.update_selected_index(KeyCode::Down);
+            return Ok(());
+        }
+        KeyCode::Char('k') | KeyCode::Up => {
+            app_state.update_selected_index(KeyCode::Up);
+            return Ok(());
+        }
+        KeyCode::Char('u') => {
+            app_state.handle_undo();
+            app_state.refresh_displayed_paths();
+            return Ok(());
+        }
+        KeyCode::Char('q') | KeyCode::Esc => {
+            app_state.exit_find_mode();
+            return Ok(());
+        }
+        _ => return Ok(()),
+    }
+}
+
 pub fn handle_key_modifier(
     key_code: KeyCode,
     modifier: KeyModifiers,
@@ -105,6 +646,9 @@ pub fn handle_key_modifier(
         match key_code {
             KeyCode::Char('c') => {
                 crossterm::terminal::disable_raw_mode()?;
+                if let Err(e) = app_state.save_session() {
+                    eprintln!("slingshot: could not save session: {}", e);
+                }
                 std::process::exit(0);
             }
             KeyCode::Char('n') => {
@@ -112,6 +656,33 @@ pub fn handle_key_modifier(
                 app_state.toggle_command_mode();
                 return Ok(());
             }
+            KeyCode::Char('g') => {
+                app_state.start_grep_mode();
+                return Ok(());
+            }
+            KeyCode::Char('r')
+                if matches!(app_state.app_mode, AppMode::FileExplorer | AppMode::History) =>
+            {
+                app_state.handle_redo();
+                return Ok(());
+            }
+            KeyCode::Char('o') if app_state.app_mode == AppMode::FileExplorer => {
+                app_state.handle_jump_back();
+                return Ok(());
+            }
+            KeyCode::Char('i') if app_state.app_mode == AppMode::FileExplorer => {
+                app_state.handle_jump_forward();
+                return Ok(());
+            }
+            KeyCode::Char('l')
+                if matches!(
+                    app_state.app_mode,
+                    AppMode::Find | AppMode::IndexSearch | AppMode::Jump
+                ) =>
+            {
+                app_state.handle_reveal();
+                return Ok(());
+            }
             _ => {
                 app_state.handle_unsupported_input();
                 return Ok(());
@@ -125,13 +696,29 @@ pub fn handle_key_modifier(
 #[cfg(test)]
 mod integration_tests {
     use super::*;
+    use crate::config;
+    use crate::deletion;
+    use crate::dirstate;
     use crate::file;
-    use crate::state_handler::AppMode;
+    use crate::finder;
+    use crate::frecency;
+    use crate::grep;
+    use crate::index;
+    use crate::journal;
+    use crate::jumplist;
+    use crate::preview;
+    use crate::register;
+    use crate::rename;
+    use crate::search;
+    use crate::selection;
+    use crate::session;
+    use crate::state_handler::{ConfirmDialog, ViewMode};
+    use crate::tree;
     use std::path::Path;
 
     fn enter_test_dir() -> AppState {
         let test_dir_path = "tests";
-        let curr_dir = std::env::current_dir().expect("Could not get current dir");
+        let curr_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
         let absolute_path = curr_dir
             .join(Path::new(test_dir_path))
             .to_str()
@@ -140,20 +727,59 @@ mod integration_tests {
 
         let paths = std::fs::read_dir(&absolute_path).expect("Could not find paths");
         let formatted_paths = file::generate_file_data(paths).expect("Error generating file data");
+        let parent_paths = file::parent_file_data(&absolute_path);
 
         return AppState {
             app_mode: AppMode::FileExplorer,
             keybind_mode: KeybindMode::Normal,
+            view_mode: ViewMode::List,
             curr_absolute_path: absolute_path,
             inner_paths: formatted_paths.clone(),
+            parent_paths,
             displayed_paths: formatted_paths.clone(),
             selected_index: 0,
+            scroll_offset: 0,
             user_input: "".to_owned(),
             message: "".to_owned(),
             command_mode: false,
+            preview: preview::Previewer::default(),
+            tree: tree::Tree::default(),
+            finder: finder::Finder::default(),
+            grep: grep::Grep::default(),
+            index: index::IndexSearch::default(),
+            frecency: frecency::Frecency::default(),
+            marks: marks::Marks::default(),
+            pending_key: None,
+            count: None,
+            list_rows: 0,
+            search: search::Search::default(),
+            visual_anchor: 0,
+            register: register::Register::default(),
+            paste: register::Paste::default(),
+            selection: selection::Selection::default(),
+            marked: selection::Selection::default(),
+            deletion: deletion::Review::default(),
+            dialog: ConfirmDialog::default(),
+            trash_items: Vec::new(),
+            journal: journal::Journal::default(),
+            rename: rename::InlineRename::default(),
+            bulk_rename: rename::BulkRename::default(),
+            command_targets: Vec::new(),
+            jump_list: jumplist::JumpList::default(),
+            dir_states: dirstate::DirStates::default(),
+            session_name: session::DEFAULT_NAME.to_owned(),
+            config: config::Config::default(),
         };
     }
 
+    fn enter_dir(state: &mut AppState, dir: &Path) {
+        let paths = std::fs::read_dir(dir).unwrap();
+        state.curr_absolute_path = dir.to_str().unwrap().to_owned();
+        state.inner_paths = file::generate_file_data(paths).unwrap();
+        state.displayed_paths = state.inner_paths.clone();
+        state.selected_index = 0;
+    }
+
     #[test]
     fn test_state_transitions() {
         let mut state = enter_test_dir();
@@ -225,8 +851,11 @@ mod integration_tests {
         handle_key(KeyCode::Esc, &mut state).unwrap();
         assert_eq!(state.keybind_mode, KeybindMode::Normal);
 
-        handle_key(KeyCode::Char('d'), &mut state).unwrap();
+        handle_key(KeyCode::Delete, &mut state).unwrap();
+        handle_key(KeyCode::Char('X'), &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Dialog);
         handle_key(KeyCode::Char('y'), &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Normal);
         println!("Deleted file");
 
         let includes_added_file: Vec<&str> = state
@@ -251,4 +880,628 @@ mod integration_tests {
         handle_key_modifier(KeyCode::Char('n'), KeyModifiers::CONTROL, &mut state).unwrap();
         assert_eq!(state.app_mode, AppMode::FileExplorer);
     }
+
+    #[test]
+    fn test_tree_mode() {
+        let mut state = enter_test_dir();
+        let initial_len = state.displayed_paths.len();
+
+        handle_key(KeyCode::Char('t'), &mut state).unwrap();
+        assert!(state.tree.enabled);
+
+        state.selected_index = state
+            .displayed_paths
+            .iter()
+            .position(|fd| fd.shortname == "dir1")
+            .unwrap();
+        handle_key(KeyCode::Char('l'), &mut state).unwrap();
+        assert_eq!(state.displayed_paths.len(), initial_len + 3);
+        assert_eq!(state.displayed_paths[state.selected_index + 1].depth, 1);
+
+        handle_key(KeyCode::Char('i'), &mut state).unwrap();
+        for ch in ['_', '2'] {
+            handle_key(KeyCode::Char(ch), &mut state).unwrap();
+        }
+        let names: Vec<&str> = state
+            .displayed_paths
+            .iter()
+            .map(|fd| fd.shortname.as_str())
+            .collect();
+        assert_eq!(names, vec!["dir1", "dir1_2.py"]);
+
+        handle_key(KeyCode::Backspace, &mut state).unwrap();
+        handle_key(KeyCode::Backspace, &mut state).unwrap();
+        handle_key(KeyCode::Esc, &mut state).unwrap();
+
+        state.selected_index += 1;
+        handle_key(KeyCode::Char('h'), &mut state).unwrap();
+        assert_eq!(state.displayed_paths.len(), initial_len);
+        assert_eq!(
+            state.displayed_paths[state.selected_index].shortname,
+            "dir1"
+        );
+    }
+
+    #[test]
+    fn test_find_mode() {
+        let mut state = enter_test_dir();
+
+        handle_key(KeyCode::Char('f'), &mut state).unwrap();
+        assert_eq!(state.app_mode, AppMode::Find);
+        while !state.finder.is_finished() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        state.poll_background();
+
+        for ch in ['d', '1', '2'] {
+            handle_key(KeyCode::Char(ch), &mut state).unwrap();
+        }
+        assert_eq!(state.displayed_paths[0].shortname, "dir1/dir1_2.py");
+
+        handle_key_modifier(KeyCode::Char('l'), KeyModifiers::CONTROL, &mut state).unwrap();
+        assert_eq!(state.app_mode, AppMode::FileExplorer);
+        assert!(state.curr_absolute_path.ends_with("tests/dir1"));
+        assert_eq!(
+            state.displayed_paths[state.selected_index].shortname,
+            "dir1_2.py"
+        );
+    }
+
+    #[test]
+    fn test_marks() {
+        let mut state = enter_test_dir();
+        let select = |state: &mut AppState, name: &str| {
+            state.selected_index = state
+                .displayed_paths
+                .iter()
+                .position(|fd| fd.shortname == name)
+                .unwrap();
+        };
+
+        select(&mut state, "dir0_1.txt");
+        handle_key(KeyCode::Char('m'), &mut state).unwrap();
+        handle_key(KeyCode::Char('a'), &mut state).unwrap();
+        assert!(state.marks.get('a').unwrap().ends_with("tests/dir0_1.txt"));
+
+        select(&mut state, "dir1");
+        handle_key(KeyCode::Char('l'), &mut state).unwrap();
+        assert!(state.curr_absolute_path.ends_with("tests/dir1"));
+
+        handle_key(KeyCode::Char('\''), &mut state).unwrap();
+        handle_key(KeyCode::Char('a'), &mut state).unwrap();
+        assert!(state.curr_absolute_path.ends_with("tests"));
+        assert_eq!(
+            state.displayed_paths[state.selected_index].shortname,
+            "dir0_1.txt"
+        );
+
+        handle_key(KeyCode::Char('\''), &mut state).unwrap();
+        handle_key(KeyCode::Char('b'), &mut state).unwrap();
+        assert_eq!(state.message, "Mark 'b' is not set");
+    }
+
+    #[test]
+    fn test_jump_list() {
+        let mut state = enter_test_dir();
+        let root = state.curr_absolute_path.clone();
+        state.selected_index = state
+            .displayed_paths
+            .iter()
+            .position(|fd| fd.shortname == "dir1")
+            .unwrap();
+
+        handle_key(KeyCode::Char('l'), &mut state).unwrap();
+        handle_key(KeyCode::Char('j'), &mut state).unwrap();
+        assert!(state.curr_absolute_path.ends_with("tests/dir1"));
+
+        handle_key_modifier(KeyCode::Char('o'), KeyModifiers::CONTROL, &mut state).unwrap();
+        assert_eq!(state.curr_absolute_path, root);
+        assert_eq!(
+            state.displayed_paths[state.selected_index].shortname,
+            "dir1"
+        );
+
+        handle_key(KeyCode::Tab, &mut state).unwrap();
+        assert!(state.curr_absolute_path.ends_with("tests/dir1"));
+        assert_eq!(state.selected_index, 1);
+
+        handle_key(KeyCode::Char('\''), &mut state).unwrap();
+        handle_key(KeyCode::Char('\''), &mut state).unwrap();
+        assert_eq!(state.curr_absolute_path, root);
+        handle_key(KeyCode::Char('\''), &mut state).unwrap();
+        handle_key(KeyCode::Char('\''), &mut state).unwrap();
+        assert!(state.curr_absolute_path.ends_with("tests/dir1"));
+    }
+
+    #[test]
+    fn test_dir_state_restored() {
+        let mut state = enter_test_dir();
+        state.selected_index = state
+            .displayed_paths
+            .iter()
+            .position(|fd| fd.shortname == "dir1")
+            .unwrap();
+
+        handle_key(KeyCode::Char('l'), &mut state).unwrap();
+        handle_key(KeyCode::Char('j'), &mut state).unwrap();
+        handle_key(KeyCode::Char('j'), &mut state).unwrap();
+        let selected = state.displayed_paths[state.selected_index]
+            .shortname
+            .clone();
+
+        handle_key(KeyCode::Char('h'), &mut state).unwrap();
+        assert_eq!(
+            state.displayed_paths[state.selected_index].shortname,
+            "dir1"
+        );
+        handle_key(KeyCode::Char('l'), &mut state).unwrap();
+        assert_eq!(
+            state.displayed_paths[state.selected_index].shortname,
+            selected
+        );
+    }
+
+    #[test]
+    fn test_history() {
+        let mut state = enter_test_dir();
+        let dir = std::env::temp_dir().join(format!("slingshot_history_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "").unwrap();
+        std::fs::write(dir.join("c.txt"), "").unwrap();
+        enter_dir(&mut state, &dir);
+        assert_eq!(state.displayed_paths[0].shortname, "a.txt");
+
+        handle_key(KeyCode::Char('u'), &mut state).unwrap();
+        assert_eq!(state.message, "Nothing to undo");
+        handle_key(KeyCode::Char('U'), &mut state).unwrap();
+        assert_eq!(state.app_mode, AppMode::FileExplorer);
+
+        handle_key(KeyCode::Char('r'), &mut state).unwrap();
+        handle_key(KeyCode::Home, &mut state).unwrap();
+        handle_key(KeyCode::Delete, &mut state).unwrap();
+        handle_key(KeyCode::Char('b'), &mut state).unwrap();
+        handle_key(KeyCode::Enter, &mut state).unwrap();
+        assert!(dir.join("b.txt").exists());
+
+        state.selected_index = 1;
+        assert_eq!(state.displayed_paths[1].shortname, "c.txt");
+        handle_key(KeyCode::Delete, &mut state).unwrap();
+        handle_key(KeyCode::Char('X'), &mut state).unwrap();
+        handle_key(KeyCode::Char('y'), &mut state).unwrap();
+        assert!(!dir.join("c.txt").exists());
+
+        handle_key(KeyCode::Char('U'), &mut state).unwrap();
+        assert_eq!(state.app_mode, AppMode::History);
+        assert_eq!(state.displayed_paths.len(), 2);
+        assert!(state.displayed_paths[0].shortname.starts_with("final"));
+        assert!(state.displayed_paths[1].shortname.starts_with("done"));
+
+        handle_key(KeyCode::Char('u'), &mut state).unwrap();
+        assert!(state.message.starts_with("Skipped"));
+        assert!(dir.join("a.txt").exists());
+        assert_eq!(state.displayed_paths.len(), 2);
+        assert!(state.displayed_paths[0].shortname.starts_with("undone"));
+        assert!(state.displayed_paths[1].shortname.starts_with("final"));
+        handle_key(KeyCode::Char('u'), &mut state).unwrap();
+        assert!(state.message.starts_with("Cannot undo"));
+        assert_eq!(state.displayed_paths.len(), 2);
+
+        handle_key_modifier(KeyCode::Char('r'), KeyModifiers::CONTROL, &mut state).unwrap();
+        assert!(dir.join("b.txt").exists());
+        assert!(state.displayed_paths[0].shortname.starts_with("done"));
+        handle_key(KeyCode::Esc, &mut state).unwrap();
+        assert_eq!(state.app_mode, AppMode::FileExplorer);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_session_restore() {
+        let mut state = enter_test_dir();
+        state.selected_index = state
+            .displayed_paths
+            .iter()
+            .position(|fd| fd.shortname == "dir1")
+            .unwrap();
+        handle_key(KeyCode::Char('l'), &mut state).unwrap();
+        handle_key(KeyCode::Char('j'), &mut state).unwrap();
+        handle_key(KeyCode::Char('s'), &mut state).unwrap();
+        handle_key(KeyCode::Char('m'), &mut state).unwrap();
+        handle_key(KeyCode::Char('q'), &mut state).unwrap();
+        let selected = state.displayed_paths[0].absolute.clone();
+        state.selection.insert(&selected);
+        state.selection.insert("/nonexistent/slingshot-gone.txt");
+        let session = state.session();
+        assert_eq!(session.selection.len(), 2);
+
+        let mut restored = enter_test_dir();
+        restored.apply_session(&session);
+        assert_eq!(restored.curr_absolute_path, state.curr_absolute_path);
+        assert_eq!(restored.selected_index, 1);
+        assert_eq!(restored.view_mode, ViewMode::Split);
+        assert_eq!(restored.marks.get('q'), state.marks.get('q'));
+        assert_eq!(restored.selection.paths(), vec![selected]);
+    }
+
+    #[test]
+    fn test_counts_and_motions() {
+        let mut state = enter_test_dir();
+        let len = state.displayed_paths.len();
+        assert!(state.displayed_paths[0].is_dir);
+
+        handle_key(KeyCode::Char('G'), &mut state).unwrap();
+        assert_eq!(state.selected_index, len - 1);
+        handle_key(KeyCode::Char('g'), &mut state).unwrap();
+        handle_key(KeyCode::Char('g'), &mut state).unwrap();
+        assert_eq!(state.selected_index, 0);
+
+        handle_key(KeyCode::Char('2'), &mut state).unwrap();
+        handle_key(KeyCode::Char('j'), &mut state).unwrap();
+        assert_eq!(state.selected_index, 2);
+        handle_key(KeyCode::Char('9'), &mut state).unwrap();
+        handle_key(KeyCode::Char('j'), &mut state).unwrap();
+        assert_eq!(state.selected_index, len - 1);
+
+        handle_key(KeyCode::Char('1'), &mut state).unwrap();
+        handle_key(KeyCode::Char('G'), &mut state).unwrap();
+        assert_eq!(state.selected_index, 1);
+
+        handle_key(KeyCode::Char('{'), &mut state).unwrap();
+        assert_eq!(state.selected_index, 0);
+        handle_key(KeyCode::Char('}'), &mut state).unwrap();
+        assert!(!state.displayed_paths[state.selected_index].is_dir);
+        assert!(state.displayed_paths[state.selected_index - 1].is_dir);
+
+        handle_key(KeyCode::Char('L'), &mut state).unwrap();
+        assert_eq!(state.selected_index, len - 1);
+        handle_key(KeyCode::Char('H'), &mut state).unwrap();
+        assert_eq!(state.selected_index, 0);
+
+        handle_key(KeyCode::Char('2'), &mut state).unwrap();
+        handle_key(KeyCode::Delete, &mut state).unwrap();
+        assert_eq!(state.marked.len(), 2);
+        assert_eq!(state.selected_index, 2);
+    }
+
+    #[test]
+    fn test_search() {
+        let mut state = enter_test_dir();
+        let name = |state: &AppState| {
+            state.displayed_paths[state.selected_index]
+                .shortname
+                .clone()
+        };
+
+        handle_key(KeyCode::Char('/'), &mut state).unwrap();
+        for c in ".py".chars() {
+            handle_key(KeyCode::Char(c), &mut state).unwrap();
+        }
+        assert_eq!(state.app_mode, AppMode::Search);
+        assert!(name(&state).ends_with(".py"));
+        handle_key(KeyCode::Enter, &mut state).unwrap();
+        assert_eq!(state.app_mode, AppMode::FileExplorer);
+        let first = state.selected_index;
+
+        handle_key(KeyCode::Char('n'), &mut state).unwrap();
+        assert!(name(&state).ends_with(".py"));
+        handle_key(KeyCode::Char('N'), &mut state).unwrap();
+        handle_key(KeyCode::Char('N'), &mut state).unwrap();
+        handle_key(KeyCode::Char('n'), &mut state).unwrap();
+        assert!(name(&state).ends_with(".py"));
+
+        state.selected_index = 0;
+        handle_key(KeyCode::Char('/'), &mut state).unwrap();
+        handle_key(KeyCode::Char('z'), &mut state).unwrap();
+        handle_key(KeyCode::Esc, &mut state).unwrap();
+        assert_eq!(state.selected_index, 0);
+
+        handle_key(KeyCode::Char('/'), &mut state).unwrap();
+        handle_key(KeyCode::Up, &mut state).unwrap();
+        assert_eq!(state.search.input, ".py");
+        handle_key(KeyCode::Enter, &mut state).unwrap();
+        assert_eq!(state.selected_index, first);
+
+        handle_key(KeyCode::Char('*'), &mut state).unwrap();
+        assert!(name(&state).ends_with(".py"));
+    }
+
+    #[test]
+    fn test_visual_mode() {
+        let mut state = enter_test_dir();
+        let len = state.displayed_paths.len();
+
+        handle_key(KeyCode::Char('v'), &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Visual);
+        handle_key(KeyCode::Char('2'), &mut state).unwrap();
+        handle_key(KeyCode::Char('j'), &mut state).unwrap();
+        assert_eq!(state.visual_range(), Some((0, 2)));
+
+        handle_key(KeyCode::Char('y'), &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Normal);
+        assert_eq!(state.register.paths.len(), 3);
+        assert!(!state.register.cut);
+        assert_eq!(state.visual_range(), None);
+
+        handle_key(KeyCode::Char('G'), &mut state).unwrap();
+        handle_key(KeyCode::Char('V'), &mut state).unwrap();
+        let (start, end) = state.visual_range().unwrap();
+        assert_eq!(end, len - 1);
+        assert!(state.displayed_paths[start - 1].is_dir);
+        assert!(!state.displayed_paths[start].is_dir);
+
+        handle_key(KeyCode::Char('d'), &mut state).unwrap();
+        assert_eq!(state.marked.len(), end - start + 1);
+
+        handle_key(KeyCode::Char('v'), &mut state).unwrap();
+        handle_key(KeyCode::Char('k'), &mut state).unwrap();
+        handle_key(KeyCode::Delete, &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Normal);
+        assert_eq!(state.marked.len(), end - start + 2);
+        assert_eq!(state.register.paths.len(), 3);
+        assert!(!state.register.cut);
+
+        handle_key(KeyCode::Char('v'), &mut state).unwrap();
+        handle_key(KeyCode::Char(':'), &mut state).unwrap();
+        assert_eq!(state.app_mode, AppMode::Command);
+        assert_eq!(state.command_targets.len(), 1);
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut state = enter_test_dir();
+        let dir = state.displayed_paths[0].absolute.clone();
+
+        handle_key(KeyCode::Char(' '), &mut state).unwrap();
+        assert!(state.selection.contains(&dir));
+        assert_eq!(state.selected_index, 1);
+
+        handle_key(KeyCode::Char('S'), &mut state).unwrap();
+        handle_key(KeyCode::Char('/'), &mut state).unwrap();
+        for c in "*.txt".chars() {
+            handle_key(KeyCode::Char(c), &mut state).unwrap();
+        }
+        handle_key(KeyCode::Enter, &mut state).unwrap();
+        assert_eq!(state.app_mode, AppMode::FileExplorer);
+        assert_eq!(state.selection.len(), 2);
+
+        handle_key(KeyCode::Char('g'), &mut state).unwrap();
+        handle_key(KeyCode::Char('g'), &mut state).unwrap();
+        handle_key(KeyCode::Char('l'), &mut state).unwrap();
+        assert_eq!(state.curr_absolute_path, dir);
+        assert_eq!(state.selection.len(), 2);
+
+        handle_key(KeyCode::Char('S'), &mut state).unwrap();
+        handle_key(KeyCode::Char('a'), &mut state).unwrap();
+        assert_eq!(state.selection.len(), 5);
+        handle_key(KeyCode::Char('S'), &mut state).unwrap();
+        handle_key(KeyCode::Char('i'), &mut state).unwrap();
+        assert_eq!(state.selection.len(), 2);
+
+        handle_key_modifier(KeyCode::Char('n'), KeyModifiers::CONTROL, &mut state).unwrap();
+        assert_eq!(state.command_targets, state.selection.paths());
+        handle_key_modifier(KeyCode::Char('n'), KeyModifiers::CONTROL, &mut state).unwrap();
+
+        handle_key(KeyCode::Esc, &mut state).unwrap();
+        handle_key(KeyCode::Char('S'), &mut state).unwrap();
+        handle_key(KeyCode::Char('c'), &mut state).unwrap();
+        assert!(state.selection.is_empty());
+    }
+
+    #[test]
+    fn test_marks_survive_filter() {
+        let mut state = enter_test_dir();
+        state.selected_index = state
+            .displayed_paths
+            .iter()
+            .position(|fd| fd.shortname == "llkh.py")
+            .unwrap();
+        let marked = state.displayed_paths[state.selected_index].absolute.clone();
+        handle_key(KeyCode::Delete, &mut state).unwrap();
+
+        handle_key(KeyCode::Char('i'), &mut state).unwrap();
+        for c in "dir".chars() {
+            handle_key(KeyCode::Char(c), &mut state).unwrap();
+        }
+        assert!(state.displayed_paths.iter().all(|fd| fd.absolute != marked));
+        for _ in 0..3 {
+            handle_key(KeyCode::Backspace, &mut state).unwrap();
+        }
+        handle_key(KeyCode::Esc, &mut state).unwrap();
+        assert!(state.marked.contains(&marked));
+
+        handle_key(KeyCode::Char('X'), &mut state).unwrap();
+        assert_eq!(state.deletion.entries.len(), 1);
+        assert_eq!(state.deletion.entries[0].path, marked);
+        handle_key(KeyCode::Esc, &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Normal);
+        assert!(state.marked.contains(&marked));
+    }
+
+    #[test]
+    fn test_deletion_review() {
+        let mut state = enter_test_dir();
+        let dir = std::env::temp_dir().join(format!("slingshot_review_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("nested/file.txt"), "").unwrap();
+        state.selection.insert(dir.to_str().unwrap());
+
+        handle_key(KeyCode::Char('X'), &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Dialog);
+        assert_eq!((state.deletion.files, state.deletion.dirs), (1, 2));
+
+        // Directories have to be confirmed by typing, so `y` is just input.
+        handle_key(KeyCode::Char('y'), &mut state).unwrap();
+        handle_key(KeyCode::Enter, &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Dialog);
+        assert!(dir.exists());
+
+        handle_key(KeyCode::Backspace, &mut state).unwrap();
+        for c in deletion::CONFIRMATION.chars() {
+            handle_key(KeyCode::Char(c), &mut state).unwrap();
+        }
+        handle_key(KeyCode::Enter, &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Normal);
+        assert!(!dir.exists());
+        assert!(state.selection.is_empty());
+
+        handle_key(KeyCode::Char('D'), &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Normal);
+        assert_eq!(state.message, "Nothing is marked for deletion");
+    }
+
+    #[test]
+    fn test_inline_rename() {
+        let mut state = enter_test_dir();
+        let dir = std::env::temp_dir().join(format!("slingshot_inline_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+        std::fs::write(dir.join("taken.txt"), "").unwrap();
+        enter_dir(&mut state, &dir);
+        assert_eq!(state.displayed_paths[0].shortname, "notes.txt");
+
+        handle_key(KeyCode::Char('c'), &mut state).unwrap();
+        handle_key(KeyCode::Char('w'), &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Rename);
+        assert_eq!(state.rename.editor.text(), "notes");
+        for _ in 0..5 {
+            handle_key(KeyCode::Backspace, &mut state).unwrap();
+        }
+        for c in "taken".chars() {
+            handle_key(KeyCode::Char(c), &mut state).unwrap();
+        }
+        handle_key(KeyCode::Enter, &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Rename);
+        assert_eq!(state.message, "taken.txt already exists");
+
+        handle_key(KeyCode::Home, &mut state).unwrap();
+        handle_key(KeyCode::Char('m'), &mut state).unwrap();
+        handle_key(KeyCode::Char('y'), &mut state).unwrap();
+        handle_key(KeyCode::Enter, &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Normal);
+        assert!(dir.join("mytaken.txt").exists());
+        assert!(!dir.join("notes.txt").exists());
+        assert_eq!(
+            state.displayed_paths[state.selected_index].shortname,
+            "mytaken.txt"
+        );
+
+        handle_key(KeyCode::Char('r'), &mut state).unwrap();
+        handle_key(KeyCode::Esc, &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Normal);
+
+        handle_key(KeyCode::Char('u'), &mut state).unwrap();
+        assert!(dir.join("notes.txt").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_bulk_rename() {
+        let mut state = enter_test_dir();
+        let dir = std::env::temp_dir().join(format!("slingshot_bulk_ui_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a"), "a").unwrap();
+        std::fs::write(dir.join("b"), "b").unwrap();
+        let paths = vec![
+            dir.join("a").to_str().unwrap().to_owned(),
+            dir.join("b").to_str().unwrap().to_owned(),
+        ];
+
+        state.review_bulk_rename(&paths, "a\na\n");
+        assert_eq!(state.keybind_mode, KeybindMode::Normal);
+        assert!(state.message.starts_with("Nothing renamed"));
+
+        state.review_bulk_rename(&paths, "b\na\n");
+        assert_eq!(state.keybind_mode, KeybindMode::Dialog);
+        assert_eq!(state.dialog.lines.len(), 2);
+        handle_key(KeyCode::Char('y'), &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Normal);
+        assert_eq!(std::fs::read_to_string(dir.join("a")).unwrap(), "b");
+        assert_eq!(std::fs::read_to_string(dir.join("b")).unwrap(), "a");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        handle_key(KeyCode::Char('u'), &mut state).unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("a")).unwrap(), "a");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_yank_and_paste() {
+        let mut state = enter_test_dir();
+        let root = std::env::temp_dir().join(format!("slingshot_yank_{}", std::process::id()));
+        let dest = root.join("dest");
+        std::fs::create_dir_all(&dest).unwrap();
+        std::fs::write(root.join("a.txt"), "a").unwrap();
+        std::fs::write(root.join("b.txt"), "b").unwrap();
+        std::fs::write(dest.join("a.txt"), "old").unwrap();
+        enter_dir(&mut state, &root);
+        assert_eq!(state.displayed_paths[1].shortname, "a.txt");
+        state.selected_index = 1;
+        handle_key(KeyCode::Char('2'), &mut state).unwrap();
+        handle_key(KeyCode::Char('y'), &mut state).unwrap();
+        handle_key(KeyCode::Char('y'), &mut state).unwrap();
+        assert_eq!(state.register.paths.len(), 2);
+        assert!(!state.register.cut);
+
+        enter_dir(&mut state, &dest);
+        handle_key(KeyCode::Char('p'), &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Conflict);
+        assert!(state.message.starts_with("a.txt already exists"));
+        handle_key(KeyCode::Char('r'), &mut state).unwrap();
+        assert_eq!(state.keybind_mode, KeybindMode::Normal);
+        assert_eq!(state.message, "Pasted 2 entries");
+        assert_eq!(std::fs::read_to_string(dest.join("a.txt")).unwrap(), "old");
+        assert_eq!(
+            std::fs::read_to_string(dest.join("a (1).txt")).unwrap(),
+            "a"
+        );
+        assert!(dest.join("b.txt").exists());
+        assert_eq!(state.register.paths.len(), 2);
+
+        std::fs::remove_file(dest.join("b.txt")).unwrap();
+        enter_dir(&mut state, &root);
+        state.selected_index = 2;
+        handle_key(KeyCode::Char('d'), &mut state).unwrap();
+        handle_key(KeyCode::Char('d'), &mut state).unwrap();
+        assert!(state.register.cut);
+        enter_dir(&mut state, &dest);
+        handle_key(KeyCode::Char('p'), &mut state).unwrap();
+        assert_eq!(state.message, "Moved 1 entries");
+        assert!(!root.join("b.txt").exists());
+        assert!(state.register.is_empty());
+
+        handle_key(KeyCode::Char('u'), &mut state).unwrap();
+        assert!(root.join("b.txt").exists());
+        assert!(!dest.join("b.txt").exists());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_parent_position() {
+        let mut state = enter_test_dir();
+        let (index, _) = state.parent_position(10);
+        let index = index.unwrap();
+        assert_eq!(state.parent_paths[index].absolute, state.curr_absolute_path);
+
+        let dir = state.parent_paths[index].absolute.clone();
+        state.selected_index = state
+            .displayed_paths
+            .iter()
+            .position(|fd| fd.shortname == "dir1")
+            .unwrap();
+        handle_key(KeyCode::Char('l'), &mut state).unwrap();
+        assert!(state
+            .parent_paths
+            .iter()
+            .any(|fd| fd.absolute == state.curr_absolute_path));
+        assert!(state.parent_paths.iter().all(|fd| fd.absolute != dir));
+
+        state.curr_absolute_path = "/".to_owned();
+        state.parent_paths = file::parent_file_data("/");
+        assert_eq!(state.parent_position(10), (None, 0));
+        assert_eq!(state.parent_position(0), (None, 0));
+    }
 }
@@ -1,24 +1,24 @@
+use crate::grep;
+use crate::layout;
+use crate::selection;
 use crate::styles;
-use crossterm::style::{Attribute, ResetColor, SetAttribute, SetForegroundColor};
-use crossterm::terminal;
+use crate::tree;
+use crossterm::style::{Attribute, Color, ResetColor, SetAttribute, SetForegroundColor};
 use crossterm::{cursor, QueueableCommand};
 use std::borrow::Cow;
 use std::cmp;
 use std::error::Error;
 use std::fs;
+use std::path::{Path, PathBuf};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct FileData {
     pub shortname: String,
     pub absolute: String,
     pub icon: String,
-    pub marked_for_deletion: bool,
-}
-
-impl FileData {
-    pub fn toggle_mark_for_deletion(&mut self) {
-        self.marked_for_deletion = !self.marked_for_deletion;
-    }
+    pub is_dir: bool,
+    pub depth: usize,
+    pub parent: Option<String>,
 }
 
 pub fn get_paths(source: &str) -> fs::ReadDir {
@@ -40,15 +40,73 @@ pub fn generate_file_data(paths: fs::ReadDir) -> Result<Vec<FileData>, Box<dyn E
                 shortname: last_index.to_owned().to_owned(),
                 absolute: path_str.clone(),
                 icon,
-                marked_for_deletion: false,
+                is_dir: path.path().is_dir(),
+                depth: 0,
+                parent: None,
             };
 
             output.push(file_data);
         }
     }
+
+    // Directories first, then files, each group in name order.
+    output.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.shortname.to_lowercase().cmp(&b.shortname.to_lowercase()))
+    });
     Ok(output)
 }
 
+pub fn file_data_for(absolute: &str, shortname: &str, is_dir: bool) -> FileData {
+    let icon = if is_dir {
+        styles::FOLDER_ICON.to_owned()
+    } else {
+        match_icon(absolute)
+    };
+
+    FileData {
+        shortname: shortname.to_owned(),
+        absolute: absolute.to_owned(),
+        icon,
+        is_dir,
+        depth: 0,
+        parent: None,
+    }
+}
+
+/// `path` itself when nothing exists there yet, otherwise the first free `name (n).ext` next to
+/// it.
+pub fn unique_path(path: &Path) -> PathBuf {
+    if path.symlink_metadata().is_err() {
+        return path.to_owned();
+    }
+
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (stem, extension) = layout::split_extension(&name);
+    (1..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| candidate.symlink_metadata().is_err())
+        .unwrap_or_else(|| path.to_owned())
+}
+
+/// Listing of the directory that contains `path`. Empty when `path` is the root or when the
+/// parent cannot be read.
+pub fn parent_file_data(path: &str) -> Vec<FileData> {
+    let parent = match Path::new(path).parent() {
+        Some(parent) => parent,
+        None => return Vec::new(),
+    };
+
+    match fs::read_dir(parent) {
+        Ok(paths) => generate_file_data(paths).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
 fn match_icon(path: &str) -> String {
     if !path.contains(".") {
         return styles::FOLDER_ICON.to_owned();
@@ -74,88 +132,252 @@ fn match_icon(path: &str) -> String {
     return styles::FILE_ICON.to_owned();
 }
 
+/// Position of `search_term` in `name`, ignoring case. This is the matcher behind the file
+/// list filter: the earlier the match, the better the entry ranks.
+pub fn match_position(name: &str, search_term: &str) -> Option<usize> {
+    name.to_lowercase().find(&search_term.to_lowercase())
+}
+
 pub fn filter_file_data(files: Cow<Vec<FileData>>, search_term: &str) -> Vec<FileData> {
-    let mut output: Vec<FileData> = files
+    let mut output: Vec<(usize, FileData)> = files
         .into_owned()
         .into_iter()
-        .filter(|fd| {
-            fd.shortname
-                .to_lowercase()
-                .contains(&search_term.to_lowercase())
-        })
+        .filter_map(|fd| match_position(&fd.shortname, search_term).map(|pos| (pos, fd)))
         .collect();
 
-    output.sort_by(|a, b| {
-        let cleaned_a_shortname = a.shortname.to_lowercase();
-        let cleaned_b_shortname = b.shortname.to_lowercase();
-        let cleaned_search_term = search_term.to_lowercase();
-
-        let a_pos = cleaned_a_shortname.find(&cleaned_search_term);
-        let b_pos = cleaned_b_shortname.find(&cleaned_search_term);
-
-        match (a_pos, b_pos) {
-            (Some(a_idx), Some(b_idx)) => a_idx.cmp(&b_idx),
-            (Some(_), None) => std::cmp::Ordering::Less,
-            (None, Some(_)) => std::cmp::Ordering::Greater,
-            _ => cleaned_a_shortname.cmp(&cleaned_b_shortname),
+    output.sort_by_key(|(pos, _)| *pos);
+    output.into_iter().map(|(_, fd)| fd).collect()
+}
+
+fn fuzzy_score_from(chars: &[char], query: &[char], start: usize) -> Option<i64> {
+    let basename_start = chars
+        .iter()
+        .rposition(|c| *c == '/')
+        .map(|pos| pos + 1)
+        .unwrap_or(0);
+
+    let mut score: i64 = 0;
+    let mut last: Option<usize> = None;
+    let mut pos = start;
+
+    for q in query {
+        let found = pos + chars[pos..].iter().position(|c| c == q)?;
+        score += 1;
+
+        if let Some(last) = last {
+            if found == last + 1 {
+                score += 5;
+            } else {
+                score -= cmp::min(found - last - 1, 5) as i64;
+            }
         }
+        if found == 0 || matches!(chars[found - 1], '/' | '_' | '-' | '.' | ' ') {
+            score += 3;
+        }
+        if found >= basename_start {
+            score += 2;
+        }
+
+        last = Some(found);
+        pos = found + 1;
+    }
+
+    Some(score - chars.len() as i64 / 10)
+}
+
+/// Scores `candidate` against `query` as a case-insensitive subsequence. Consecutive
+/// characters, matches at word boundaries and matches inside the file name score higher,
+/// while gaps and long paths are penalised. Returns None when `query` does not match.
+pub fn fuzzy_score(candidate: &str, query: &str) -> Option<i64> {
+    let chars: Vec<char> = candidate.to_lowercase().chars().collect();
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+
+    let first = match query.first() {
+        Some(first) => first,
+        None => return Some(0),
+    };
+
+    chars
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| *c == first)
+        .filter_map(|(start, _)| fuzzy_score_from(&chars, &query, start))
+        .max()
+}
+
+/// Keeps the best `limit` entries of `files` that fuzzy match `query`, best first.
+pub fn rank_fuzzy(files: &[FileData], query: &str, limit: usize) -> Vec<FileData> {
+    let mut scored: Vec<(i64, &FileData)> = files
+        .iter()
+        .filter_map(|fd| fuzzy_score(&fd.shortname, query).map(|score| (score, fd)))
+        .collect();
+
+    scored.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .cmp(a_score)
+            .then_with(|| a.shortname.cmp(&b.shortname))
     });
-    output
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, fd)| fd.clone())
+        .collect()
 }
 
-pub fn print_file_data(paths: Cow<Vec<FileData>>, index: usize, stdout: &mut std::io::Stdout) {
-    let mut line_index = 1;
-    let (_, height) = terminal::size().unwrap();
-    let t_height = cmp::max(height, 1) - 1;
-    let height_limit = t_height - 1;
+fn entry_details(path: &FileData) -> String {
+    match fs::symlink_metadata(&path.absolute) {
+        Ok(metadata) if metadata.is_file() => layout::format_size(metadata.len()),
+        _ => "".to_owned(),
+    }
+}
 
-    stdout.queue(cursor::MoveToRow(1)).unwrap();
-    for (i, path) in paths.iter().enumerate() {
-        stdout.queue(cursor::MoveToRow(line_index)).unwrap();
-        if i == index && i <= height_limit as usize {
+/// Extra styling of the entries in a list.
+#[derive(Clone, Copy, Default)]
+pub struct Decorations<'a> {
+    /// Search pattern whose matches are underlined.
+    pub highlight: Option<&'a grep::Pattern>,
+    /// Inclusive range of entries selected in visual mode.
+    pub visual: Option<(usize, usize)>,
+    /// Entries picked for multi-file operations, drawn in their own color.
+    pub selected: Option<&'a selection::Selection>,
+    /// Entries marked for deletion, drawn in the error color.
+    pub marked: Option<&'a selection::Selection>,
+}
+
+/// Prints `name` in `color` with every match of `highlight` underlined.
+fn print_name(name: &str, color: Color, highlight: Option<&grep::Pattern>) {
+    let ranges = highlight.map(|p| p.find_all(name)).unwrap_or_default();
+    let mut pos = 0;
+
+    print!("{}", SetForegroundColor(color));
+    for (start, end) in ranges {
+        print!(
+            "{}{}{}{}{}{}",
+            &name[pos..start],
+            SetAttribute(Attribute::Underlined),
+            SetForegroundColor(styles::NUMBER),
+            &name[start..end],
+            SetAttribute(Attribute::NoUnderline),
+            SetForegroundColor(color)
+        );
+        pos = end;
+    }
+    print!("{}{}", &name[pos..], ResetColor);
+}
+
+pub fn print_file_data(
+    paths: Cow<Vec<FileData>>,
+    index: usize,
+    offset: usize,
+    decorations: Decorations,
+    area: layout::Rect,
+    stdout: &mut std::io::Stdout,
+) {
+    let visible: Vec<(usize, &FileData)> = paths
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(area.height as usize)
+        .collect();
+
+    let guides = tree::guides(&paths);
+    let details: Vec<String> = visible.iter().map(|(_, fd)| entry_details(fd)).collect();
+    let icon_width = layout::max_width(visible.iter().map(|(_, fd)| fd.icon.as_str()));
+    let details_width = layout::max_width(details.iter().map(|d| d.as_str()));
+    let columns =
+        layout::allocate_columns(area.width as usize, paths.len(), icon_width, details_width);
+
+    for (row, (i, path)) in visible.iter().enumerate() {
+        let y = area.y + row as u16;
+        let guide = &guides[*i];
+        let name = layout::truncate_middle(
+            &path.shortname,
+            columns.name.saturating_sub(layout::str_width(guide)),
+        );
+
+        let highlight = decorations.highlight;
+        let in_visual = decorations
+            .visual
+            .is_some_and(|(start, end)| (start..=end).contains(i));
+        let is_selected = decorations
+            .selected
+            .is_some_and(|selection| selection.contains(&path.absolute));
+        let is_marked = decorations
+            .marked
+            .is_some_and(|marked| marked.contains(&path.absolute));
+
+        stdout.queue(cursor::MoveTo(area.x, y)).unwrap();
+        if in_visual {
+            print!("{}", SetAttribute(Attribute::Reverse));
+        }
+        if *i == index {
             print!("{}{}{}", SetAttribute(Attribute::Bold), i, ResetColor);
 
-            stdout.queue(cursor::MoveToColumn(3)).unwrap();
+            stdout
+                .queue(cursor::MoveTo(area.x + columns.index as u16, y))
+                .unwrap();
+            print!(
+                "{}{}{}",
+                SetForegroundColor(styles::LIGHT_CONTRAST),
+                guide,
+                ResetColor
+            );
             print!("{}{}", path.icon, ResetColor);
 
-            let fg_color = if path.marked_for_deletion {
+            let fg_color = if is_marked {
                 styles::ERR
+            } else if is_selected {
+                styles::PROPERTY
             } else {
                 styles::DEFAULT
             };
 
-            print!(
-                "{}{}{}{}",
-                SetAttribute(Attribute::Bold),
-                SetForegroundColor(fg_color),
-                path.shortname,
-                ResetColor
-            );
-        } else if path.marked_for_deletion && i <= height_limit as usize {
+            print!("{}", SetAttribute(Attribute::Bold));
+            print_name(&name, fg_color, highlight);
+        } else if is_marked {
             print!("{}{}{}", SetAttribute(Attribute::Bold), i, ResetColor);
 
-            stdout.queue(cursor::MoveToColumn(3)).unwrap();
-            print!("{}{}", path.icon, ResetColor);
+            stdout
+                .queue(cursor::MoveTo(area.x + columns.index as u16, y))
+                .unwrap();
             print!(
                 "{}{}{}",
-                SetForegroundColor(styles::ERR),
-                path.shortname,
+                SetForegroundColor(styles::LIGHT_CONTRAST),
+                guide,
                 ResetColor
             );
-        } else if i <= height_limit as usize {
+            print!("{}{}", path.icon, ResetColor);
+            print_name(&name, styles::ERR, highlight);
+        } else {
             print!("{}{}", SetForegroundColor(styles::LIGHT_CONTRAST), i);
 
-            stdout.queue(cursor::MoveToColumn(3)).unwrap();
-            print!("{}", path.icon);
+            stdout
+                .queue(cursor::MoveTo(area.x + columns.index as u16, y))
+                .unwrap();
+            print!("{}{}", guide, path.icon);
+            let fg_color = if is_selected {
+                styles::PROPERTY
+            } else {
+                styles::LIGHT_CONTRAST
+            };
+            print_name(&name, fg_color, highlight);
+        }
+
+        if in_visual {
+            print!("{}", SetAttribute(Attribute::NoReverse));
+        }
+
+        if columns.details > 0 {
+            let details_x =
+                area.x + (area.width as usize - layout::str_width(&details[row])) as u16;
+            stdout.queue(cursor::MoveTo(details_x, y)).unwrap();
             print!(
                 "{}{}{}",
                 SetForegroundColor(styles::LIGHT_CONTRAST),
-                path.shortname,
+                details[row],
                 ResetColor
             );
         }
-        line_index += 1;
-        stdout.queue(cursor::MoveToColumn(0)).unwrap();
     }
 }
 
@@ -173,7 +395,9 @@ mod file_tests {
                 shortname: shortname.to_owned(),
                 absolute: "test-absolute".to_owned(),
                 icon: "test-icon".to_owned(),
-                marked_for_deletion: false,
+                is_dir: false,
+                depth: 0,
+                parent: None,
             };
             test_file_input.push(file_data);
         }
@@ -207,4 +431,51 @@ mod file_tests {
             }
         }
     }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("src/main.rs", "xyz"), None);
+        assert_eq!(fuzzy_score("src/main.rs", "msr"), None);
+        assert!(fuzzy_score("src/main.rs", "smr").is_some());
+
+        let consecutive = fuzzy_score("src/main.rs", "main").unwrap();
+        let scattered = fuzzy_score("src/my_app/index.rs", "main").unwrap();
+        assert!(consecutive > scattered);
+
+        let basename = fuzzy_score("tests/state.rs", "state").unwrap();
+        let directory = fuzzy_score("state/tests.rs", "state").unwrap();
+        assert!(basename > directory);
+    }
+
+    #[test]
+    fn test_rank_fuzzy() {
+        let files: Vec<FileData> = ["docs/readme.md", "src/file.rs", "src/finder.rs", "Makefile"]
+            .iter()
+            .map(|name| file_data_for(name, name, false))
+            .collect();
+
+        let ranked: Vec<String> = rank_fuzzy(&files, "fi", 10)
+            .into_iter()
+            .map(|fd| fd.shortname)
+            .collect();
+        assert_eq!(ranked, vec!["src/file.rs", "src/finder.rs", "Makefile"]);
+        assert_eq!(rank_fuzzy(&files, "fi", 1).len(), 1);
+    }
+
+    #[test]
+    fn test_unique_path() {
+        let dir = std::env::temp_dir().join(format!("slingshot_unique_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(unique_path(&dir.join("file.txt")), dir.join("file.txt"));
+
+        fs::write(dir.join("file.txt"), "").unwrap();
+        fs::write(dir.join("file (1).txt"), "").unwrap();
+        assert_eq!(unique_path(&dir.join("file.txt")), dir.join("file (2).txt"));
+        assert_eq!(
+            unique_path(&dir.join("file (1).txt")),
+            dir.join("file (1) (1).txt")
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
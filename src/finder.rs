@@ -0,0 +1,173 @@
+use crate::config::Config;
+use crate::file::{self, FileData};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub const MAX_RESULTS: usize = 1000;
+
+/// Walks a directory tree on a background thread, collecting every entry with its path
+/// relative to `root` as the short name.
+#[derive(Clone, Default)]
+pub struct Finder {
+    pub root: String,
+    results: Arc<Mutex<Vec<FileData>>>,
+    cancelled: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    seen: usize,
+    seen_finished: bool,
+}
+
+impl Finder {
+    pub fn start(root: &str, config: &Config) -> Finder {
+        let finder = Finder {
+            root: root.to_owned(),
+            ..Finder::default()
+        };
+
+        let root = root.to_owned();
+        let config = config.clone();
+        let results = Arc::clone(&finder.results);
+        let cancelled = Arc::clone(&finder.cancelled);
+        let finished = Arc::clone(&finder.finished);
+
+        thread::spawn(move || {
+            walk(&root, &config, &cancelled, &results);
+            finished.store(true, Ordering::SeqCst);
+        });
+
+        finder
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::SeqCst)
+    }
+
+    pub fn len(&self) -> usize {
+        self.results
+            .lock()
+            .map(|results| results.len())
+            .unwrap_or(0)
+    }
+
+    /// Returns true when new results arrived or the walk finished since the last call.
+    pub fn poll(&mut self) -> bool {
+        let len = self.len();
+        let finished = self.is_finished();
+        let changed = len != self.seen || finished != self.seen_finished;
+        self.seen = len;
+        self.seen_finished = finished;
+        changed
+    }
+
+    pub fn ranked(&self, query: &str) -> Vec<FileData> {
+        match self.results.lock() {
+            Ok(results) => file::rank_fuzzy(&results, query, MAX_RESULTS),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// Collects the entries below `root`, skipping ignored names and stopping at
+/// `config.max_depth`. Symbolic links to directories are listed but never followed.
+pub fn walk(root: &str, config: &Config, cancelled: &AtomicBool, results: &Mutex<Vec<FileData>>) {
+    let mut stack: Vec<(PathBuf, String, usize)> = vec![(PathBuf::from(root), String::new(), 0)];
+
+    while let Some((dir, relative, depth)) = stack.pop() {
+        if cancelled.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        let mut batch = Vec::new();
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if config.is_ignored(&name) {
+                continue;
+            }
+
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            let shortname = if relative.is_empty() {
+                name
+            } else {
+                format!("{}/{}", relative, name)
+            };
+            let absolute = entry.path().to_string_lossy().into_owned();
+
+            if is_dir && depth + 1 < config.max_depth {
+                stack.push((entry.path(), shortname.clone(), depth + 1));
+            }
+            batch.push(file::file_data_for(&absolute, &shortname, is_dir));
+        }
+
+        if let Ok(mut results) = results.lock() {
+            results.extend(batch);
+        }
+    }
+}
+
+#[cfg(test)]
+mod finder_tests {
+    use super::*;
+
+    fn walk_names(config: &Config) -> Vec<String> {
+        let root = format!("{}/tests", env!("CARGO_MANIFEST_DIR"));
+        let results = Mutex::new(Vec::new());
+        walk(&root, config, &AtomicBool::new(false), &results);
+
+        let mut names: Vec<String> = results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|fd| fd.shortname)
+            .filter(|name| name != "testing.py")
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_walk() {
+        assert_eq!(
+            walk_names(&Config::default()),
+            vec![
+                "dir0_1.txt",
+                "dir1",
+                "dir1/dir1_0.txt",
+                "dir1/dir1_1.txt",
+                "dir1/dir1_2.py",
+                "llkh.py",
+            ]
+        );
+
+        let shallow = Config {
+            max_depth: 1,
+            ..Config::default()
+        };
+        assert_eq!(walk_names(&shallow), vec!["dir0_1.txt", "dir1", "llkh.py"]);
+
+        let ignoring = Config {
+            ignore: vec!["dir1".to_owned()],
+            ..Config::default()
+        };
+        assert_eq!(walk_names(&ignoring), vec!["dir0_1.txt", "llkh.py"]);
+    }
+
+    #[test]
+    fn test_cancelled_walk() {
+        let root = format!("{}/tests", env!("CARGO_MANIFEST_DIR"));
+        let results = Mutex::new(Vec::new());
+        walk(&root, &Config::default(), &AtomicBool::new(true), &results);
+        assert!(results.into_inner().unwrap().is_empty());
+    }
+}
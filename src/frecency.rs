@@ -0,0 +1,359 @@
+use crate::config;
+use crate::file::{self, FileData};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// Once the ranks add up to more than this, every rank decays so that directories that are
+/// no longer visited eventually drop out of the database.
+const MAX_TOTAL_RANK: f64 = 10_000.0;
+const AGING_FACTOR: f64 = 0.9;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub rank: f64,
+    pub last_visit: u64,
+}
+
+impl Entry {
+    /// The visit count weighted by how recently the directory was last visited.
+    pub fn score(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_visit);
+        let weight = if age < HOUR {
+            4.0
+        } else if age < DAY {
+            2.0
+        } else if age < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        self.rank * weight
+    }
+}
+
+/// Visited directories with their frecency data, stored one `rank\tlast_visit\tpath` line
+/// per directory in `$XDG_DATA_HOME/slingshot/frecency`. A database without a path lives in
+/// memory only.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frecency {
+    pub path: Option<PathBuf>,
+    pub entries: BTreeMap<String, Entry>,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn database_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("frecency"))
+}
+
+/// Whether every term appears in `path` in order, with the last term inside the final path
+/// component. Terms containing an uppercase ASCII letter are matched case-sensitively.
+fn matches_terms(path: &str, terms: &[&str]) -> bool {
+    let basename_start = path.rfind('/').map(|i| i + 1).unwrap_or(0);
+    let mut pos = 0;
+
+    for (i, term) in terms.iter().enumerate() {
+        let found = if term.chars().any(|c| c.is_ascii_uppercase()) {
+            path[pos..].find(term)
+        } else {
+            path[pos..]
+                .to_ascii_lowercase()
+                .find(&term.to_ascii_lowercase())
+        };
+
+        match found {
+            Some(start) => pos += start + term.len(),
+            None => return false,
+        }
+        if i == terms.len() - 1 && pos <= basename_start {
+            return false;
+        }
+    }
+    true
+}
+
+impl Frecency {
+    pub fn load(path: &Path) -> Frecency {
+        let contents = fs::read_to_string(path).unwrap_or_default();
+        Frecency {
+            path: Some(path.to_owned()),
+            entries: Frecency::parse(&contents),
+        }
+    }
+
+    fn parse(contents: &str) -> BTreeMap<String, Entry> {
+        contents
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let rank = fields.next()?.parse().ok()?;
+                let last_visit = fields.next()?.parse().ok()?;
+                let path = fields.next()?.to_owned();
+                Some((path, Entry { rank, last_visit }))
+            })
+            .collect()
+    }
+
+    fn serialize(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(path, entry)| format!("{}\t{}\t{}\n", entry.rank, entry.last_visit, path))
+            .collect()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::File::create(&tmp)?.write_all(self.serialize().as_bytes())?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    fn age(&mut self) {
+        let total: f64 = self.entries.values().map(|entry| entry.rank).sum();
+        if total <= MAX_TOTAL_RANK {
+            return;
+        }
+        for entry in self.entries.values_mut() {
+            entry.rank *= AGING_FACTOR;
+        }
+        self.entries.retain(|_, entry| entry.rank >= 1.0);
+    }
+
+    /// Merges the entries saved by other instances into this database, keeping the larger rank
+    /// and the later visit of a directory both know.
+    fn merge(&mut self, entries: BTreeMap<String, Entry>) {
+        for (path, entry) in entries {
+            match self.entries.get_mut(&path) {
+                Some(current) => {
+                    current.rank = current.rank.max(entry.rank);
+                    current.last_visit = current.last_visit.max(entry.last_visit);
+                }
+                None => {
+                    self.entries.insert(path, entry);
+                }
+            }
+        }
+    }
+
+    /// Takes the lock next to the database file, held until the returned file is dropped.
+    fn lock(&self) -> Option<fs::File> {
+        let path = self.path.as_ref()?;
+        fs::create_dir_all(path.parent()?).ok()?;
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path.with_extension("lock"))
+            .ok()?;
+        file.lock().ok()?;
+        Some(file)
+    }
+
+    /// Records a visit to `dir` at `now` and writes the database back to disk. Under the lock,
+    /// the database on disk is merged in first so that visits recorded by other instances in
+    /// the meantime are kept.
+    pub fn visit(&mut self, dir: &str, now: u64) -> Result<(), Box<dyn Error>> {
+        let _lock = self.lock();
+        if let Some(contents) = self
+            .path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+        {
+            self.merge(Frecency::parse(&contents));
+        }
+        let entry = self.entries.entry(dir.to_owned()).or_insert(Entry {
+            rank: 0.0,
+            last_visit: now,
+        });
+        entry.rank += 1.0;
+        entry.last_visit = now;
+        self.age();
+        self.save()
+    }
+
+    /// Directories matching every term, best score first.
+    pub fn query(&self, terms: &[&str], now: u64) -> Vec<(String, f64)> {
+        let mut output: Vec<(String, f64)> = self
+            .entries
+            .iter()
+            .filter(|(path, _)| matches_terms(path, terms))
+            .map(|(path, entry)| (path.clone(), entry.score(now)))
+            .collect();
+        output.sort_by(|a, b| b.1.total_cmp(&a.1));
+        output
+    }
+
+    /// Matching directories that still exist, as file data for the jump overlay.
+    pub fn ranked(&self, search_term: &str) -> Vec<FileData> {
+        let terms: Vec<&str> = search_term.split_whitespace().collect();
+        self.query(&terms, now())
+            .into_iter()
+            .filter(|(path, _)| Path::new(path).is_dir())
+            .map(|(path, _)| file::file_data_for(&path, &config::abbreviate_home(&path), true))
+            .collect()
+    }
+}
+
+/// Entry point of `slingshot query <terms>`: prints the best matching directory.
+pub fn run_query_command(terms: &[String]) -> Result<(), Box<dyn Error>> {
+    let path = database_path().ok_or("Could not find the data directory")?;
+    let terms: Vec<&str> = terms.iter().map(|term| term.as_str()).collect();
+
+    let best = Frecency::load(&path)
+        .query(&terms, now())
+        .into_iter()
+        .find(|(dir, _)| Path::new(dir).is_dir());
+
+    match best {
+        Some((dir, _)) => {
+            println!("{}", dir);
+            Ok(())
+        }
+        None => Err("No matching directory".into()),
+    }
+}
+
+#[cfg(test)]
+mod frecency_tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_terms() {
+        assert!(matches_terms("/home/user/code/slingshot", &["slingshot"]));
+        assert!(matches_terms(
+            "/home/user/code/slingshot",
+            &["code", "sling"]
+        ));
+        assert!(!matches_terms(
+            "/home/user/code/slingshot",
+            &["sling", "code"]
+        ));
+        assert!(!matches_terms("/home/user/code/slingshot", &["code"]));
+        assert!(!matches_terms("/home/user/code/slingshot", &["Sling"]));
+        assert!(matches_terms("/home/user/code/slingshot", &[]));
+    }
+
+    #[test]
+    fn test_visit_and_query() {
+        let mut db = Frecency::default();
+        let now = 10 * WEEK;
+
+        db.visit("/old/project", now - 2 * WEEK).unwrap();
+        db.visit("/old/project", now - 2 * WEEK).unwrap();
+        db.visit("/old/project", now - 2 * WEEK).unwrap();
+        db.visit("/new/project", now - 60).unwrap();
+
+        let ranked: Vec<String> = db
+            .query(&["project"], now)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(ranked, vec!["/new/project", "/old/project"]);
+        assert_eq!(db.entries["/old/project"].rank, 3.0);
+
+        let roundtrip = Frecency::parse(&db.serialize());
+        assert_eq!(roundtrip, db.entries);
+    }
+
+    #[test]
+    fn test_concurrent_instances() {
+        let dir = std::env::temp_dir().join(format!("slingshot_frecency_{}", std::process::id()));
+        let path = dir.join("frecency");
+        let mut first = Frecency::load(&path);
+        let mut second = Frecency::load(&path);
+
+        first.visit("/a", 1).unwrap();
+        second.visit("/b", 2).unwrap();
+        first.visit("/a", 3).unwrap();
+
+        let merged = Frecency::load(&path);
+        assert_eq!(merged.entries.len(), 2);
+        assert_eq!(merged.entries["/a"].rank, 2.0);
+        assert_eq!(merged.entries["/b"].rank, 1.0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_visit_keeps_unsaved_entries() {
+        let dir = std::env::temp_dir().join(format!("slingshot_unsaved_{}", std::process::id()));
+        let path = dir.join("frecency");
+        let mut other = Frecency::load(&path);
+        other.visit("/shared", 5).unwrap();
+        other.visit("/shared", 6).unwrap();
+
+        let mut db = Frecency::load(&path);
+        db.entries.insert(
+            "/unsaved".to_owned(),
+            Entry {
+                rank: 3.0,
+                last_visit: 4,
+            },
+        );
+        db.entries.insert(
+            "/shared".to_owned(),
+            Entry {
+                rank: 1.0,
+                last_visit: 9,
+            },
+        );
+        db.visit("/a", 10).unwrap();
+
+        let saved = Frecency::load(&path);
+        assert_eq!(saved.entries.len(), 3);
+        assert_eq!(saved.entries["/unsaved"].rank, 3.0);
+        assert_eq!(
+            saved.entries["/shared"],
+            Entry {
+                rank: 2.0,
+                last_visit: 9,
+            }
+        );
+        assert_eq!(saved.entries["/a"].rank, 1.0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_aging() {
+        let mut db = Frecency::default();
+        db.entries.insert(
+            "/busy".to_owned(),
+            Entry {
+                rank: MAX_TOTAL_RANK,
+                last_visit: 0,
+            },
+        );
+        db.entries.insert(
+            "/rare".to_owned(),
+            Entry {
+                rank: 1.0,
+                last_visit: 0,
+            },
+        );
+
+        db.visit("/busy", 1).unwrap();
+        assert!(db.entries["/busy"].rank < MAX_TOTAL_RANK);
+        assert!(!db.entries.contains_key("/rare"));
+    }
+}
@@ -0,0 +1,423 @@
+use crate::config::Config;
+use crate::finder;
+use crate::layout;
+use crate::styles;
+use crossterm::style::{Attribute, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::{cursor, QueueableCommand};
+use std::fs;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const MAX_FILE_BYTES: u64 = 8 * 1024 * 1024;
+const BINARY_CHECK_BYTES: usize = 8 * 1024;
+const MAX_MATCHES: usize = 10_000;
+const MAX_SNIPPET_CHARS: usize = 400;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrepMatch {
+    pub absolute: String,
+    pub relative: String,
+    pub line: usize,
+    pub snippet: String,
+}
+
+/// A plain text pattern. Lowercase patterns match case-insensitively, any uppercase letter
+/// makes the search case-sensitive.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pattern {
+    text: String,
+    ignore_case: bool,
+}
+
+impl Pattern {
+    pub fn new(text: &str) -> Pattern {
+        let ignore_case = !text.chars().any(|c| c.is_ascii_uppercase());
+        Pattern {
+            text: text.to_owned(),
+            ignore_case,
+        }
+    }
+
+    /// Byte ranges of every occurrence of the pattern in `line`.
+    pub fn find_all(&self, line: &str) -> Vec<(usize, usize)> {
+        if self.text.is_empty() {
+            return Vec::new();
+        }
+
+        let haystack = if self.ignore_case {
+            line.to_ascii_lowercase()
+        } else {
+            line.to_owned()
+        };
+        let needle = if self.ignore_case {
+            self.text.to_ascii_lowercase()
+        } else {
+            self.text.clone()
+        };
+
+        haystack
+            .match_indices(&needle)
+            .map(|(start, found)| (start, start + found.len()))
+            .collect()
+    }
+}
+
+/// Searches file contents below a directory with a pool of worker threads, streaming
+/// matches as they are found.
+#[derive(Clone, Default)]
+pub struct Grep {
+    pub root: String,
+    pub pattern: Pattern,
+    matches: Arc<Mutex<Vec<GrepMatch>>>,
+    cancelled: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    truncated: Arc<AtomicBool>,
+    seen: usize,
+    seen_finished: bool,
+}
+
+impl Grep {
+    pub fn start(root: &str, pattern: &str, config: &Config) -> Grep {
+        let grep = Grep {
+            root: root.to_owned(),
+            pattern: Pattern::new(pattern),
+            ..Grep::default()
+        };
+
+        let root = root.to_owned();
+        let pattern = grep.pattern.clone();
+        let config = config.clone();
+        let matches = Arc::clone(&grep.matches);
+        let cancelled = Arc::clone(&grep.cancelled);
+        let finished = Arc::clone(&grep.finished);
+        let truncated = Arc::clone(&grep.truncated);
+
+        thread::spawn(move || {
+            let stopped = search(&root, &pattern, &config, &cancelled, &matches);
+            truncated.store(stopped, Ordering::SeqCst);
+            finished.store(true, Ordering::SeqCst);
+        });
+
+        grep
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::SeqCst)
+    }
+
+    /// Whether the search stopped at `MAX_MATCHES` rather than looking at every file.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated.load(Ordering::SeqCst)
+    }
+
+    pub fn len(&self) -> usize {
+        self.matches
+            .lock()
+            .map(|matches| matches.len())
+            .unwrap_or(0)
+    }
+
+    pub fn get(&self, index: usize) -> Option<GrepMatch> {
+        self.matches
+            .lock()
+            .ok()
+            .and_then(|matches| matches.get(index).cloned())
+    }
+
+    pub fn snapshot(&self) -> Vec<GrepMatch> {
+        self.matches
+            .lock()
+            .map(|matches| matches.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns true when new matches arrived or the search finished since the last call.
+    pub fn poll(&mut self) -> bool {
+        let len = self.len();
+        let finished = self.is_finished();
+        let changed = len != self.seen || finished != self.seen_finished;
+        self.seen = len;
+        self.seen_finished = finished;
+        changed
+    }
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_CHECK_BYTES)].contains(&0)
+}
+
+/// Matching lines of a single file. Binary and oversized files produce no matches.
+pub fn search_file(absolute: &str, relative: &str, pattern: &Pattern) -> Vec<GrepMatch> {
+    let mut bytes = Vec::new();
+    let read = fs::File::open(absolute).and_then(|file| {
+        if file.metadata()?.len() > MAX_FILE_BYTES {
+            return Ok(0);
+        }
+        file.take(MAX_FILE_BYTES).read_to_end(&mut bytes)
+    });
+
+    if read.is_err() || is_binary(&bytes) {
+        return Vec::new();
+    }
+
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !pattern.find_all(line).is_empty())
+        .map(|(i, line)| GrepMatch {
+            absolute: absolute.to_owned(),
+            relative: relative.to_owned(),
+            line: i + 1,
+            snippet: line.trim().chars().take(MAX_SNIPPET_CHARS).collect(),
+        })
+        .collect()
+}
+
+/// Scans every file below `root` that is not ignored by `config`, splitting the work across
+/// one worker per available CPU. Returns true when it stopped after the first `MAX_MATCHES`
+/// matches.
+pub fn search(
+    root: &str,
+    pattern: &Pattern,
+    config: &Config,
+    cancelled: &AtomicBool,
+    matches: &Mutex<Vec<GrepMatch>>,
+) -> bool {
+    let entries = Mutex::new(Vec::new());
+    finder::walk(root, config, cancelled, &entries);
+
+    let mut files: Vec<(String, String)> = entries
+        .into_inner()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|fd| !fd.is_dir)
+        .map(|fd| (fd.absolute, fd.shortname))
+        .collect();
+    files.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let queue = Mutex::new(files);
+    let truncated = AtomicBool::new(false);
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                if cancelled.load(Ordering::SeqCst) {
+                    return;
+                }
+
+                let next = queue.lock().ok().and_then(|mut queue| queue.pop());
+                let (absolute, relative) = match next {
+                    Some(next) => next,
+                    None => return,
+                };
+
+                let found = search_file(&absolute, &relative, pattern);
+                if found.is_empty() {
+                    continue;
+                }
+
+                if let Ok(mut matches) = matches.lock() {
+                    let room = MAX_MATCHES - matches.len();
+                    if found.len() > room {
+                        matches.extend(found.into_iter().take(room));
+                        truncated.store(true, Ordering::SeqCst);
+                        cancelled.store(true, Ordering::SeqCst);
+                        return;
+                    }
+                    matches.extend(found);
+                }
+            });
+        }
+    });
+    truncated.into_inner()
+}
+
+fn print_snippet(snippet: &str, pattern: &Pattern, width: usize) {
+    let ranges = pattern.find_all(snippet);
+
+    // Keep the first match on screen when it sits past the visible part of the line.
+    let mut start = 0;
+    if let Some((first, _)) = ranges.first() {
+        if layout::str_width(&snippet[..*first]) > width / 2 {
+            start = snippet[..*first]
+                .char_indices()
+                .rev()
+                .nth(10)
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+        }
+    }
+
+    let mut remaining = width;
+    let mut pos = start;
+    if start > 0 {
+        print!("…");
+        remaining = remaining.saturating_sub(1);
+    }
+
+    for (match_start, match_end) in ranges.into_iter().filter(|(s, _)| *s >= start) {
+        let before = layout::take_width(&snippet[pos..match_start], remaining);
+        print!("{}", before);
+        remaining -= layout::str_width(before);
+
+        let found = layout::take_width(&snippet[match_start..match_end], remaining);
+        print!(
+            "{}{}{}{}",
+            SetAttribute(Attribute::Bold),
+            SetForegroundColor(styles::ERR),
+            found,
+            ResetColor
+        );
+        remaining -= layout::str_width(found);
+        pos = match_end;
+    }
+    print!("{}", layout::take_width(&snippet[pos..], remaining));
+}
+
+/// Prints results as `path:line: snippet`, with every occurrence of the pattern highlighted.
+pub fn print_matches(
+    matches: &[GrepMatch],
+    pattern: &Pattern,
+    index: usize,
+    offset: usize,
+    area: layout::Rect,
+    stdout: &mut std::io::Stdout,
+) {
+    let width = area.width as usize;
+    let visible = matches
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(area.height as usize);
+
+    for (row, (i, found)) in visible.enumerate() {
+        stdout
+            .queue(cursor::MoveTo(area.x, area.y + row as u16))
+            .unwrap();
+
+        let location = format!(":{}: ", found.line);
+        let path_width = (width * 2 / 5).saturating_sub(layout::str_width(&location));
+        let path = layout::truncate_middle(&found.relative, path_width);
+        let color = if i == index {
+            styles::DEFAULT
+        } else {
+            styles::LIGHT_CONTRAST
+        };
+
+        if i == index {
+            print!("{}", SetAttribute(Attribute::Bold));
+        }
+        print!(
+            "{}{}{}{}{}",
+            SetForegroundColor(color),
+            path,
+            SetForegroundColor(styles::NUMBER),
+            location,
+            SetForegroundColor(color)
+        );
+
+        let used = layout::str_width(&path) + layout::str_width(&location);
+        print_snippet(&found.snippet, pattern, width.saturating_sub(used));
+        print!("{}", ResetColor);
+    }
+}
+
+#[cfg(test)]
+mod grep_tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("slingshot_grep_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::write(dir.join("src/main.rs"), "fn main() {\n    run_app();\n}\n").unwrap();
+        fs::write(dir.join("src/app.rs"), "pub fn run_app() {}\n// RUN_APP\n").unwrap();
+        fs::write(dir.join("target/build.rs"), "run_app();\n").unwrap();
+        fs::write(dir.join("blob.bin"), b"run_app\0\x01\x02").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_pattern() {
+        let pattern = Pattern::new("app");
+        assert_eq!(pattern.find_all("App and app"), vec![(0, 3), (8, 11)]);
+
+        let pattern = Pattern::new("App");
+        assert_eq!(pattern.find_all("App and app"), vec![(0, 3)]);
+        assert!(Pattern::new("").find_all("anything").is_empty());
+    }
+
+    #[test]
+    fn test_search() {
+        let dir = fixture_dir();
+        let matches = Mutex::new(Vec::new());
+        search(
+            dir.to_str().unwrap(),
+            &Pattern::new("run_app"),
+            &Config::default(),
+            &AtomicBool::new(false),
+            &matches,
+        );
+
+        let mut found: Vec<(String, usize, String)> = matches
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.relative, m.line, m.snippet))
+            .collect();
+        found.sort();
+
+        assert_eq!(
+            found,
+            vec![
+                ("src/app.rs".to_owned(), 1, "pub fn run_app() {}".to_owned()),
+                ("src/app.rs".to_owned(), 2, "// RUN_APP".to_owned()),
+                ("src/main.rs".to_owned(), 2, "run_app();".to_owned()),
+            ]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_search_truncated() {
+        let dir = std::env::temp_dir().join(format!("slingshot_grep_max_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("many.txt"), "hit\n".repeat(MAX_MATCHES + 1)).unwrap();
+
+        let matches = Mutex::new(Vec::new());
+        let root = dir.to_str().unwrap();
+        let cancelled = AtomicBool::new(false);
+        assert!(search(
+            root,
+            &Pattern::new("hit"),
+            &Config::default(),
+            &cancelled,
+            &matches
+        ));
+        assert_eq!(matches.lock().unwrap().len(), MAX_MATCHES);
+
+        let matches = Mutex::new(Vec::new());
+        let cancelled = AtomicBool::new(false);
+        assert!(!search(
+            root,
+            &Pattern::new("miss"),
+            &Config::default(),
+            &cancelled,
+            &matches
+        ));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,612 @@
+use crate::layout;
+use crate::styles;
+use crossterm::style::Color;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    String,
+    Number,
+    Comment,
+    Type,
+    Function,
+    Property,
+}
+
+impl TokenKind {
+    pub fn color(&self) -> Color {
+        match self {
+            TokenKind::Plain => styles::DEFAULT,
+            TokenKind::Keyword => styles::KEYWORD,
+            TokenKind::String => styles::STRING,
+            TokenKind::Number => styles::NUMBER,
+            TokenKind::Comment => styles::LIGHT_CONTRAST,
+            TokenKind::Type => styles::TYPE,
+            TokenKind::Function => styles::FUNCTION,
+            TokenKind::Property => styles::PROPERTY,
+        }
+    }
+}
+
+pub type Span = (TokenKind, String);
+pub type Line = Vec<Span>;
+
+pub struct Syntax {
+    keywords: &'static [&'static str],
+    line_comment: Option<&'static str>,
+    blocks: &'static [(&'static str, &'static str, TokenKind)],
+    string_delims: &'static [char],
+    key_separator: Option<char>,
+    sections: bool,
+}
+
+const C_BLOCKS: &[(&str, &str, TokenKind)] = &[("/*", "*/", TokenKind::Comment)];
+
+static RUST: Syntax = Syntax {
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ],
+    line_comment: Some("//"),
+    blocks: C_BLOCKS,
+    string_delims: &['"'],
+    key_separator: None,
+    sections: false,
+};
+
+static PYTHON: Syntax = Syntax {
+    keywords: &[
+        "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+        "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+        "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return",
+        "self", "try", "while", "with", "yield",
+    ],
+    line_comment: Some("#"),
+    blocks: &[
+        ("\"\"\"", "\"\"\"", TokenKind::String),
+        ("'''", "'''", TokenKind::String),
+    ],
+    string_delims: &['"', '\''],
+    key_separator: None,
+    sections: false,
+};
+
+static JAVASCRIPT: Syntax = Syntax {
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "import",
+        "in",
+        "instanceof",
+        "let",
+        "new",
+        "null",
+        "of",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "typeof",
+        "undefined",
+        "var",
+        "void",
+        "while",
+        "yield",
+    ],
+    line_comment: Some("//"),
+    blocks: C_BLOCKS,
+    string_delims: &['"', '\'', '`'],
+    key_separator: None,
+    sections: false,
+};
+
+static TYPESCRIPT: Syntax = Syntax {
+    keywords: &[
+        "abstract",
+        "any",
+        "as",
+        "async",
+        "await",
+        "boolean",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "declare",
+        "default",
+        "else",
+        "enum",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "implements",
+        "import",
+        "in",
+        "interface",
+        "keyof",
+        "let",
+        "new",
+        "null",
+        "number",
+        "of",
+        "private",
+        "protected",
+        "public",
+        "readonly",
+        "return",
+        "string",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "type",
+        "typeof",
+        "undefined",
+        "void",
+        "while",
+    ],
+    line_comment: Some("//"),
+    blocks: C_BLOCKS,
+    string_delims: &['"', '\'', '`'],
+    key_separator: None,
+    sections: false,
+};
+
+static C: Syntax = Syntax {
+    keywords: &[
+        "auto",
+        "bool",
+        "break",
+        "case",
+        "char",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "define",
+        "delete",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "extern",
+        "false",
+        "float",
+        "for",
+        "if",
+        "include",
+        "inline",
+        "int",
+        "long",
+        "namespace",
+        "new",
+        "nullptr",
+        "private",
+        "public",
+        "return",
+        "short",
+        "signed",
+        "sizeof",
+        "static",
+        "struct",
+        "switch",
+        "template",
+        "this",
+        "true",
+        "typedef",
+        "union",
+        "unsigned",
+        "using",
+        "virtual",
+        "void",
+        "volatile",
+        "while",
+    ],
+    line_comment: Some("//"),
+    blocks: C_BLOCKS,
+    string_delims: &['"', '\''],
+    key_separator: None,
+    sections: false,
+};
+
+static GO: Syntax = Syntax {
+    keywords: &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "fallthrough",
+        "false",
+        "for",
+        "func",
+        "go",
+        "goto",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "nil",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "true",
+        "type",
+        "var",
+    ],
+    line_comment: Some("//"),
+    blocks: C_BLOCKS,
+    string_delims: &['"', '\'', '`'],
+    key_separator: None,
+    sections: false,
+};
+
+static ZIG: Syntax = Syntax {
+    keywords: &[
+        "and",
+        "break",
+        "catch",
+        "comptime",
+        "const",
+        "continue",
+        "defer",
+        "else",
+        "enum",
+        "errdefer",
+        "error",
+        "export",
+        "false",
+        "fn",
+        "for",
+        "if",
+        "inline",
+        "null",
+        "or",
+        "orelse",
+        "pub",
+        "return",
+        "struct",
+        "switch",
+        "test",
+        "true",
+        "try",
+        "undefined",
+        "union",
+        "unreachable",
+        "var",
+        "while",
+    ],
+    line_comment: Some("//"),
+    blocks: &[],
+    string_delims: &['"', '\''],
+    key_separator: None,
+    sections: false,
+};
+
+static TOML: Syntax = Syntax {
+    keywords: &["true", "false"],
+    line_comment: Some("#"),
+    blocks: &[
+        ("\"\"\"", "\"\"\"", TokenKind::String),
+        ("'''", "'''", TokenKind::String),
+    ],
+    string_delims: &['"', '\''],
+    key_separator: Some('='),
+    sections: true,
+};
+
+static JSON: Syntax = Syntax {
+    keywords: &["true", "false", "null"],
+    line_comment: None,
+    blocks: &[],
+    string_delims: &['"'],
+    key_separator: Some(':'),
+    sections: false,
+};
+
+/// Picks the syntax for the languages that have an icon in `styles::ICONS`.
+pub fn syntax_for(path: &str) -> Option<&'static Syntax> {
+    let (_, ext) = layout::split_extension(path);
+    match ext.trim_start_matches('.') {
+        "rs" => Some(&RUST),
+        "py" => Some(&PYTHON),
+        "js" => Some(&JAVASCRIPT),
+        "ts" => Some(&TYPESCRIPT),
+        "c" | "h" | "cpp" | "hpp" => Some(&C),
+        "go" => Some(&GO),
+        "zig" => Some(&ZIG),
+        "toml" => Some(&TOML),
+        "json" => Some(&JSON),
+        _ => None,
+    }
+}
+
+fn push(spans: &mut Line, kind: TokenKind, text: &str) {
+    if let Some((last_kind, last_text)) = spans.last_mut() {
+        if *last_kind == kind {
+            last_text.push_str(text);
+            return;
+        }
+    }
+    spans.push((kind, text.to_owned()));
+}
+
+/// Byte length of a quoted string starting at the beginning of `rest`, including both quotes.
+fn string_len(rest: &str, delim: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in rest.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delim {
+            return i + c.len_utf8();
+        }
+    }
+    rest.len()
+}
+
+fn word_len(rest: &str, accept: impl Fn(char) -> bool) -> usize {
+    rest.char_indices()
+        .find(|(_, c)| !accept(*c))
+        .map(|(i, _)| i)
+        .unwrap_or(rest.len())
+}
+
+fn is_key(after: &str, syntax: &Syntax) -> bool {
+    match syntax.key_separator {
+        Some(separator) => after.trim_start().starts_with(separator),
+        None => false,
+    }
+}
+
+fn tokenize_line(line: &str, syntax: &Syntax, open_block: &mut Option<usize>) -> Line {
+    let mut spans: Line = Vec::new();
+    let mut i = 0;
+
+    if let Some(block) = *open_block {
+        let (_, end, kind) = syntax.blocks[block];
+        match line.find(end) {
+            Some(pos) => {
+                i = pos + end.len();
+                push(&mut spans, kind, &line[..i]);
+                *open_block = None;
+            }
+            None => {
+                push(&mut spans, kind, line);
+                return spans;
+            }
+        }
+    }
+
+    if syntax.sections && line.trim_start().starts_with('[') {
+        push(&mut spans, TokenKind::Type, line);
+        return spans;
+    }
+
+    while i < line.len() {
+        let rest = &line[i..];
+
+        if let Some(comment) = syntax.line_comment {
+            if rest.starts_with(comment) {
+                push(&mut spans, TokenKind::Comment, rest);
+                break;
+            }
+        }
+
+        let block = syntax
+            .blocks
+            .iter()
+            .position(|(start, _, _)| rest.starts_with(start));
+        if let Some(block) = block {
+            let (start, end, kind) = syntax.blocks[block];
+            match rest[start.len()..].find(end) {
+                Some(pos) => {
+                    let len = start.len() + pos + end.len();
+                    push(&mut spans, kind, &rest[..len]);
+                    i += len;
+                }
+                None => {
+                    push(&mut spans, kind, rest);
+                    *open_block = Some(block);
+                    break;
+                }
+            }
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap_or(' ');
+        let (kind, len) = if syntax.string_delims.contains(&c) {
+            let len = string_len(rest, c);
+            if is_key(&rest[len..], syntax) {
+                (TokenKind::Property, len)
+            } else {
+                (TokenKind::String, len)
+            }
+        } else if c.is_ascii_digit() {
+            let len = word_len(rest, |c| c.is_ascii_alphanumeric() || c == '.' || c == '_');
+            (TokenKind::Number, len)
+        } else if c.is_alphabetic() || c == '_' {
+            let dashes = syntax.key_separator.is_some();
+            let len = word_len(rest, |c| {
+                c.is_alphanumeric() || c == '_' || (dashes && c == '-')
+            });
+            let word = &rest[..len];
+            let after = &rest[len..];
+            let kind = if syntax.keywords.contains(&word) {
+                TokenKind::Keyword
+            } else if is_key(after, syntax) {
+                TokenKind::Property
+            } else if after.starts_with('(') || after.starts_with('!') {
+                TokenKind::Function
+            } else if c.is_uppercase() {
+                TokenKind::Type
+            } else {
+                TokenKind::Plain
+            };
+            (kind, len)
+        } else {
+            (TokenKind::Plain, c.len_utf8())
+        };
+
+        push(&mut spans, kind, &rest[..len]);
+        i += len;
+    }
+
+    spans
+}
+
+/// Splits each line into colored tokens. Block comments and multi-line strings carry over
+/// from one line to the next.
+pub fn highlight(lines: &[String], syntax: Option<&Syntax>) -> Vec<Line> {
+    let syntax = match syntax {
+        Some(syntax) => syntax,
+        None => {
+            return lines
+                .iter()
+                .map(|line| vec![(TokenKind::Plain, line.clone())])
+                .collect()
+        }
+    };
+
+    let mut open_block = None;
+    lines
+        .iter()
+        .map(|line| tokenize_line(line, syntax, &mut open_block))
+        .collect()
+}
+
+#[cfg(test)]
+mod highlight_tests {
+    use super::*;
+
+    fn kinds_of(line: &Line, kind: TokenKind) -> Vec<&str> {
+        line.iter()
+            .filter(|(k, _)| *k == kind)
+            .map(|(_, text)| text.trim())
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight_rust() {
+        let lines = vec![
+            "pub fn main() -> Result<(), Box<dyn Error>> { // entry".to_owned(),
+            "    let x = \"a \\\" b\"; println!(\"{}\", 42);".to_owned(),
+        ];
+        let highlighted = highlight(&lines, syntax_for("src/main.rs"));
+
+        assert_eq!(
+            kinds_of(&highlighted[0], TokenKind::Keyword),
+            vec!["pub", "fn", "dyn"]
+        );
+        assert_eq!(kinds_of(&highlighted[0], TokenKind::Function), vec!["main"]);
+        assert_eq!(
+            kinds_of(&highlighted[0], TokenKind::Type),
+            vec!["Result", "Box", "Error"]
+        );
+        assert_eq!(
+            kinds_of(&highlighted[0], TokenKind::Comment),
+            vec!["// entry"]
+        );
+        assert_eq!(
+            kinds_of(&highlighted[1], TokenKind::String),
+            vec!["\"a \\\" b\"", "\"{}\""]
+        );
+        assert_eq!(kinds_of(&highlighted[1], TokenKind::Number), vec!["42"]);
+    }
+
+    #[test]
+    fn test_highlight_multiline_blocks() {
+        let lines = vec![
+            "def f():".to_owned(),
+            "    \"\"\"docs".to_owned(),
+            "    more docs\"\"\" # done".to_owned(),
+            "    return None".to_owned(),
+        ];
+        let highlighted = highlight(&lines, syntax_for("script.py"));
+
+        assert_eq!(
+            highlighted[2][0],
+            (TokenKind::String, "    more docs\"\"\"".to_owned())
+        );
+        assert_eq!(
+            kinds_of(&highlighted[2], TokenKind::Comment),
+            vec!["# done"]
+        );
+        assert_eq!(
+            kinds_of(&highlighted[3], TokenKind::Keyword),
+            vec!["return", "None"]
+        );
+    }
+
+    #[test]
+    fn test_highlight_config_files() {
+        let toml = vec![
+            "[dependencies]".to_owned(),
+            "crossterm = \"0.26.1\"".to_owned(),
+        ];
+        let highlighted = highlight(&toml, syntax_for("Cargo.toml"));
+        assert_eq!(
+            highlighted[0],
+            vec![(TokenKind::Type, "[dependencies]".to_owned())]
+        );
+        assert_eq!(
+            kinds_of(&highlighted[1], TokenKind::Property),
+            vec!["crossterm"]
+        );
+
+        let json = vec!["{\"name\": \"slingshot\", \"tags\": null}".to_owned()];
+        let highlighted = highlight(&json, syntax_for("package.json"));
+        assert_eq!(
+            kinds_of(&highlighted[0], TokenKind::Property),
+            vec!["\"name\"", "\"tags\""]
+        );
+        assert_eq!(
+            kinds_of(&highlighted[0], TokenKind::String),
+            vec!["\"slingshot\""]
+        );
+        assert_eq!(kinds_of(&highlighted[0], TokenKind::Keyword), vec!["null"]);
+
+        assert!(syntax_for("notes.txt").is_none());
+    }
+}
@@ -0,0 +1,378 @@
+use crate::config::{self, Config};
+use crate::file::{self, FileData};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+const HEADER: &str = "slingshot-index 1";
+const MAX_RESULTS: usize = 1000;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DirRecord {
+    pub mtime: u64,
+    pub entries: Vec<(String, bool)>,
+}
+
+/// Every directory below the configured roots with the names it contained the last time it
+/// was scanned. A directory is only read again when its modification time changes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Index {
+    pub dirs: BTreeMap<String, DirRecord>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct UpdateStats {
+    pub scanned: usize,
+    pub reused: usize,
+}
+
+pub fn index_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("index"))
+}
+
+fn mtime(path: &str) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    modified
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+fn read_entries(dir: &str, config: &Config) -> Vec<(String, bool)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut output: Vec<(String, bool)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if config.is_ignored(&name) || name.contains('\n') {
+                return None;
+            }
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            Some((name, is_dir))
+        })
+        .collect();
+    output.sort();
+    output
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+impl Index {
+    /// Builds a new index for `roots`, reusing the entries of every directory whose
+    /// modification time did not change since `self` was built.
+    pub fn update(&self, roots: &[String], config: &Config) -> (Index, UpdateStats) {
+        let mut index = Index::default();
+        let mut stats = UpdateStats::default();
+        let mut stack: Vec<String> = roots.to_vec();
+
+        while let Some(dir) = stack.pop() {
+            if index.dirs.contains_key(&dir) {
+                continue;
+            }
+
+            let mtime = match mtime(&dir) {
+                Some(mtime) => mtime,
+                None => continue,
+            };
+
+            let record = match self.dirs.get(&dir) {
+                // The entries were read under the previous config, so names ignored since then
+                // are dropped here rather than kept until the directory changes.
+                Some(record) if record.mtime == mtime => {
+                    stats.reused += 1;
+                    DirRecord {
+                        mtime,
+                        entries: record
+                            .entries
+                            .iter()
+                            .filter(|(name, _)| !config.is_ignored(name))
+                            .cloned()
+                            .collect(),
+                    }
+                }
+                _ => {
+                    stats.scanned += 1;
+                    DirRecord {
+                        mtime,
+                        entries: read_entries(&dir, config),
+                    }
+                }
+            };
+
+            for (name, is_dir) in &record.entries {
+                if *is_dir {
+                    stack.push(join(&dir, name));
+                }
+            }
+            index.dirs.insert(dir, record);
+        }
+
+        (index, stats)
+    }
+
+    pub fn len(&self) -> usize {
+        self.dirs.values().map(|record| record.entries.len()).sum()
+    }
+
+    pub fn parse(contents: &str) -> Index {
+        let mut index = Index::default();
+        let mut lines = contents.lines();
+        if lines.next() != Some(HEADER) {
+            return index;
+        }
+
+        let mut current: Option<String> = None;
+        for line in lines {
+            if let Some(rest) = line.strip_prefix("D ") {
+                if let Some((mtime, dir)) = rest.split_once(' ') {
+                    let mtime = mtime.parse().unwrap_or(0);
+                    index.dirs.insert(
+                        dir.to_owned(),
+                        DirRecord {
+                            mtime,
+                            entries: Vec::new(),
+                        },
+                    );
+                    current = Some(dir.to_owned());
+                }
+            } else if let (Some(dir), Some((kind, name))) = (&current, line.split_once(' ')) {
+                if let Some(record) = index.dirs.get_mut(dir) {
+                    record.entries.push((name.to_owned(), kind == "d"));
+                }
+            }
+        }
+        index
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut output = format!("{}\n", HEADER);
+        for (dir, record) in &self.dirs {
+            output.push_str(&format!("D {} {}\n", record.mtime, dir));
+            for (name, is_dir) in &record.entries {
+                let kind = if *is_dir { "d" } else { "f" };
+                output.push_str(&format!("{} {}\n", kind, name));
+            }
+        }
+        output
+    }
+
+    pub fn load(path: &Path) -> Index {
+        fs::read_to_string(path)
+            .map(|contents| Index::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    /// Writes the index next to its final location first so that a crash never leaves a
+    /// truncated index behind.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::File::create(&tmp)?.write_all(self.serialize().as_bytes())?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// Every indexed entry as file data, shown by its full path with the home directory
+    /// abbreviated to `~`.
+    pub fn entries(&self) -> Vec<FileData> {
+        let mut output = Vec::with_capacity(self.len());
+        for (dir, record) in &self.dirs {
+            for (name, is_dir) in &record.entries {
+                let absolute = join(dir, name);
+                let shortname = config::abbreviate_home(&absolute);
+                output.push(file::file_data_for(&absolute, &shortname, *is_dir));
+            }
+        }
+        output
+    }
+}
+
+/// Index entries held in memory by the TUI.
+#[derive(Clone, Default)]
+pub struct IndexSearch {
+    entries: Arc<Vec<FileData>>,
+}
+
+impl IndexSearch {
+    pub fn load(path: &Path) -> IndexSearch {
+        IndexSearch {
+            entries: Arc::new(Index::load(path).entries()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Matches file names with the same matcher as the file list filter, so an entry whose
+    /// name starts with the search term ranks first.
+    pub fn search(&self, search_term: &str) -> Vec<FileData> {
+        let mut matches: Vec<(usize, usize, &FileData)> = self
+            .entries
+            .iter()
+            .filter_map(|fd| {
+                let name = fd.absolute.rsplit('/').next().unwrap_or(&fd.absolute);
+                file::match_position(name, search_term).map(|pos| (pos, fd.absolute.len(), fd))
+            })
+            .collect();
+
+        matches.sort_by_key(|(pos, len, _)| (*pos, *len));
+        matches
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, _, fd)| fd.clone())
+            .collect()
+    }
+}
+
+/// Entry point of `slingshot index`.
+pub fn run_index_command() -> Result<(), Box<dyn Error>> {
+    let config = Config::load();
+    let path = index_path().ok_or("Could not find the data directory")?;
+
+    let previous = Index::load(&path);
+    let (index, stats) = previous.update(&config.index_roots, &config);
+    index.save(&path)?;
+
+    println!(
+        "Indexed {} entries in {} directories ({} rescanned, {} unchanged) into {}",
+        index.len(),
+        index.dirs.len(),
+        stats.scanned,
+        stats.reused,
+        path.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod index_tests {
+    use super::*;
+
+    fn fixture_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("slingshot_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src/nested")).unwrap();
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join("src/main.rs"), "").unwrap();
+        fs::write(dir.join("src/nested/domain.rs"), "").unwrap();
+        fs::write(dir.join("README.md"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_update_and_roundtrip() {
+        let dir = fixture_dir("index");
+        let roots = vec![dir.to_str().unwrap().to_owned()];
+        let config = Config::default();
+
+        let (index, stats) = Index::default().update(&roots, &config);
+        assert_eq!(
+            stats,
+            UpdateStats {
+                scanned: 3,
+                reused: 0
+            }
+        );
+        assert_eq!(index.len(), 5);
+        assert_eq!(Index::parse(&index.serialize()), index);
+
+        let (again, stats) = index.update(&roots, &config);
+        assert_eq!(
+            stats,
+            UpdateStats {
+                scanned: 0,
+                reused: 3
+            }
+        );
+        assert_eq!(again, index);
+
+        let stale = Index {
+            dirs: index
+                .dirs
+                .iter()
+                .map(|(dir, record)| {
+                    let record = DirRecord {
+                        mtime: record.mtime.saturating_sub(1),
+                        ..record.clone()
+                    };
+                    (dir.clone(), record)
+                })
+                .collect(),
+        };
+        let (_, stats) = stale.update(&roots, &config);
+        assert_eq!(
+            stats,
+            UpdateStats {
+                scanned: 3,
+                reused: 0
+            }
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_ignore_change() {
+        let dir = fixture_dir("index_ignore");
+        let roots = vec![dir.to_str().unwrap().to_owned()];
+        let (index, _) = Index::default().update(&roots, &Config::default());
+        assert!(index.dirs.contains_key(&join(&roots[0], "src/nested")));
+
+        let config = Config::parse("ignore = nested");
+        let (index, stats) = index.update(&roots, &config);
+        assert_eq!(
+            stats,
+            UpdateStats {
+                scanned: 0,
+                reused: 2
+            }
+        );
+        assert!(!index.dirs.contains_key(&join(&roots[0], "src/nested")));
+        assert_eq!(
+            index.dirs[&join(&roots[0], "src")].entries,
+            vec![("main.rs".to_owned(), false)]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_search() {
+        let dir = fixture_dir("index_search");
+        let (index, _) =
+            Index::default().update(&[dir.to_str().unwrap().to_owned()], &Config::default());
+        let search = IndexSearch {
+            entries: Arc::new(index.entries()),
+        };
+
+        let names: Vec<String> = search
+            .search("main")
+            .into_iter()
+            .map(|fd| fd.absolute.rsplit('/').next().unwrap().to_owned())
+            .collect();
+        assert_eq!(names, vec!["main.rs", "domain.rs"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
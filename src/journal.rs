@@ -0,0 +1,416 @@
+use crate::register;
+use crate::trash;
+use std::error::Error;
+use std::path::Path;
+
+const MAX_ENTRIES: usize = 100;
+
+/// A single filesystem change, with what is needed to invert it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    /// `original` was moved into the trash, where it is now at `trashed`.
+    Trash { original: String, trashed: String },
+    /// `path` was deleted for good.
+    Delete { path: String },
+    /// `from` was renamed or moved to `to`.
+    Rename { from: String, to: String },
+    /// `path` was created, copied or restored. While undone it waits in the trash at `trashed`.
+    Create {
+        path: String,
+        trashed: Option<String>,
+    },
+}
+
+/// Everything one command changed, undone and redone as a whole.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub description: String,
+    pub operations: Vec<Operation>,
+}
+
+/// Commands that can be undone, most recent last, and the ones that were undone and can be
+/// redone.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Journal {
+    pub done: Vec<Entry>,
+    pub undone: Vec<Entry>,
+}
+
+fn move_path(from: &str, to: &str) -> Result<(), Box<dyn Error>> {
+    if Path::new(to).symlink_metadata().is_ok() {
+        return Err(format!("{} already exists", to).into());
+    }
+    register::move_path(Path::new(from), Path::new(to))
+        .map_err(|e| format!("Could not move {}: {}", from, e))?;
+    Ok(())
+}
+
+fn to_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+impl Operation {
+    /// Inverts the change, returning the operation that redoes it.
+    fn undo(&self) -> Result<Operation, Box<dyn Error>> {
+        match self {
+            Operation::Trash { original, trashed } => {
+                trash::untrash(Path::new(trashed), Path::new(original))?;
+                Ok(self.clone())
+            }
+            Operation::Delete { path } => {
+                Err(format!("{} was deleted permanently and cannot be restored", path).into())
+            }
+            Operation::Rename { from, to } => {
+                move_path(to, from)?;
+                Ok(self.clone())
+            }
+            Operation::Create { path, .. } => {
+                let trashed = trash::trash_path(path)?;
+                Ok(Operation::Create {
+                    path: path.clone(),
+                    trashed: Some(to_string(&trashed)),
+                })
+            }
+        }
+    }
+
+    /// Applies the change again, returning the operation that undoes it.
+    fn redo(&self) -> Result<Operation, Box<dyn Error>> {
+        match self {
+            Operation::Trash { original, .. } => {
+                let trashed = trash::trash_path(original)?;
+                Ok(Operation::Trash {
+                    original: original.clone(),
+                    trashed: to_string(&trashed),
+                })
+            }
+            Operation::Delete { path } => Err(format!("{} cannot be deleted again", path).into()),
+            Operation::Rename { from, to } => {
+                move_path(from, to)?;
+                Ok(self.clone())
+            }
+            Operation::Create { path, trashed } => {
+                let trashed = trashed
+                    .as_ref()
+                    .ok_or_else(|| format!("{} is not in the trash", path))?;
+                trash::untrash(Path::new(trashed), Path::new(path))?;
+                Ok(Operation::Create {
+                    path: path.clone(),
+                    trashed: None,
+                })
+            }
+        }
+    }
+
+    fn can_undo(&self) -> bool {
+        !matches!(self, Operation::Delete { .. })
+    }
+}
+
+/// Runs `apply` on every operation, most recent first when `reverse` is set. Returns the
+/// operations that went through (in their original order) and the first error, keeping the
+/// operations that did not go through in `operations`.
+fn apply_all(
+    operations: &mut Vec<Operation>,
+    reverse: bool,
+    apply: impl Fn(&Operation) -> Result<Operation, Box<dyn Error>>,
+) -> (Vec<Operation>, Option<String>) {
+    let mut applied = Vec::new();
+    let mut failed = Vec::new();
+    let mut error = None;
+
+    let pending = std::mem::take(operations);
+    let ordered: Vec<Operation> = if reverse {
+        pending.into_iter().rev().collect()
+    } else {
+        pending
+    };
+    for operation in ordered {
+        match apply(&operation) {
+            Ok(inverse) => applied.push(inverse),
+            Err(e) => {
+                error.get_or_insert_with(|| e.to_string());
+                failed.push(operation);
+            }
+        }
+    }
+
+    if reverse {
+        applied.reverse();
+        failed.reverse();
+    }
+    *operations = failed;
+    (applied, error)
+}
+
+impl Journal {
+    /// Remembers a finished command. Anything undone before can no longer be redone.
+    pub fn record(&mut self, description: &str, operations: Vec<Operation>) {
+        if operations.is_empty() {
+            return;
+        }
+        self.undone.clear();
+        self.done.push(Entry {
+            description: description.to_owned(),
+            operations,
+        });
+        if self.done.len() > MAX_ENTRIES {
+            self.done.remove(0);
+        }
+    }
+
+    /// Undoes the most recent command that can be undone and returns a message describing what
+    /// happened. Commands that only deleted entries permanently stay in the history, and the
+    /// message names them and any permanently deleted entry that could not be restored.
+    /// Operations that fail stay on the undo stack so that they can be retried.
+    pub fn undo(&mut self) -> String {
+        let position = match self.done.iter().rposition(|entry| {
+            entry
+                .operations
+                .iter()
+                .any(|operation| operation.can_undo())
+        }) {
+            Some(position) => position,
+            None => {
+                return match self.done.last() {
+                    Some(entry) => format!(
+                        "Cannot undo \"{}\": permanently deleted entries cannot be restored",
+                        entry.description
+                    ),
+                    None => "Nothing to undo".to_owned(),
+                }
+            }
+        };
+        let passed: Vec<String> = self.done[position + 1..]
+            .iter()
+            .rev()
+            .map(|entry| format!("\"{}\"", entry.description))
+            .collect();
+        let mut entry = self.done.remove(position);
+        let (mut operations, deleted): (Vec<Operation>, Vec<Operation>) = entry
+            .operations
+            .drain(..)
+            .partition(|operation| operation.can_undo());
+
+        let (applied, error) = apply_all(&mut operations, true, Operation::undo);
+        if !applied.is_empty() {
+            self.undone.push(Entry {
+                description: entry.description.clone(),
+                operations: applied,
+            });
+        }
+        let mut message = match &error {
+            Some(error) => format!("Could not fully undo \"{}\": {}", entry.description, error),
+            None => format!("Undid \"{}\"", entry.description),
+        };
+        if !passed.is_empty() {
+            message = format!(
+                "Skipped {}, which cannot be undone. {}",
+                passed.join(", "),
+                message
+            );
+        }
+        if !deleted.is_empty() {
+            let paths: Vec<&str> = deleted
+                .iter()
+                .filter_map(|operation| match operation {
+                    Operation::Delete { path } => Some(path.as_str()),
+                    _ => None,
+                })
+                .collect();
+            message.push_str(&format!(
+                "; not restored, deleted permanently: {}",
+                paths.join(", ")
+            ));
+        }
+        if error.is_some() {
+            operations.extend(deleted);
+            entry.operations = operations;
+            self.done.insert(position, entry);
+        }
+        message
+    }
+
+    /// Redoes the most recently undone command, like `undo`.
+    pub fn redo(&mut self) -> String {
+        let mut entry = match self.undone.pop() {
+            Some(entry) => entry,
+            None => return "Nothing to redo".to_owned(),
+        };
+
+        let (applied, error) = apply_all(&mut entry.operations, false, Operation::redo);
+        if !applied.is_empty() {
+            self.done.push(Entry {
+                description: entry.description.clone(),
+                operations: applied,
+            });
+        }
+        match error {
+            Some(error) => {
+                let message = format!("Could not fully redo \"{}\": {}", entry.description, error);
+                self.undone.push(entry);
+                message
+            }
+            None => format!("Redid \"{}\"", entry.description),
+        }
+    }
+
+    /// Descriptions of every command, most recent first, with undone commands (which redo
+    /// would apply next) listed above them.
+    pub fn history(&self) -> Vec<String> {
+        let undone = self
+            .undone
+            .iter()
+            .map(|entry| format!("undone  {}", entry.description));
+        let done = self.done.iter().rev().map(|entry| {
+            let state = if entry
+                .operations
+                .iter()
+                .any(|operation| operation.can_undo())
+            {
+                "done"
+            } else {
+                "final"
+            };
+            format!("{:<8}{}", state, entry.description)
+        });
+        undone.chain(done).collect()
+    }
+}
+
+#[cfg(test)]
+mod journal_tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_undo_trash() {
+        let root = std::env::temp_dir().join(format!("slingshot_journal_{}", std::process::id()));
+        let trash = trash::Trash::new(&root.join("Trash"));
+        fs::create_dir_all(&root).unwrap();
+        let original = to_string(&root.join("a.txt"));
+        fs::write(&original, "a").unwrap();
+        let trashed = to_string(&trash.put(Path::new(&original)).unwrap());
+
+        let mut journal = Journal::default();
+        let operation = Operation::Trash {
+            original: original.clone(),
+            trashed,
+        };
+        journal.record("Moved 1 entries to the trash", vec![operation.clone()]);
+
+        fs::write(&original, "b").unwrap();
+        assert!(journal.undo().starts_with("Could not fully undo"));
+        assert_eq!(journal.done.len(), 1);
+        assert!(journal.undone.is_empty());
+
+        fs::remove_file(&original).unwrap();
+        assert_eq!(journal.undo(), "Undid \"Moved 1 entries to the trash\"");
+        assert_eq!(fs::read_to_string(&original).unwrap(), "a");
+        assert!(trash.items().is_empty());
+        assert_eq!(journal.undone[0].operations, vec![operation]);
+        assert_eq!(
+            journal.history(),
+            vec!["undone  Moved 1 entries to the trash"]
+        );
+        assert_eq!(journal.undo(), "Nothing to undo");
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_rename_undo_redo() {
+        let dir = std::env::temp_dir().join(format!("slingshot_journal_mv_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let from = to_string(&dir.join("a.txt"));
+        let to = to_string(&dir.join("b.txt"));
+        fs::write(&to, "").unwrap();
+
+        let mut journal = Journal::default();
+        journal.record(
+            "Rename a.txt",
+            vec![Operation::Rename {
+                from: from.clone(),
+                to: to.clone(),
+            }],
+        );
+        assert_eq!(journal.undo(), "Undid \"Rename a.txt\"");
+        assert!(Path::new(&from).exists());
+
+        fs::write(&to, "").unwrap();
+        assert!(journal.redo().starts_with("Could not fully redo"));
+        assert_eq!(journal.undone.len(), 1);
+        fs::remove_file(&to).unwrap();
+        assert_eq!(journal.redo(), "Redid \"Rename a.txt\"");
+        assert!(Path::new(&to).exists());
+        assert_eq!(journal.history(), vec!["done    Rename a.txt"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_permanent_deletion() {
+        let dir = std::env::temp_dir().join(format!("slingshot_journal_rm_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let a = to_string(&dir.join("a.txt"));
+        let b = to_string(&dir.join("b.txt"));
+        fs::write(&b, "").unwrap();
+
+        let mut journal = Journal::default();
+        journal.record(
+            "Rename a.txt",
+            vec![Operation::Rename {
+                from: a.clone(),
+                to: b.clone(),
+            }],
+        );
+        journal.record(
+            "Deleted 1 entries",
+            vec![Operation::Delete {
+                path: "/gone".to_owned(),
+            }],
+        );
+        assert_eq!(
+            journal.history(),
+            vec!["final   Deleted 1 entries", "done    Rename a.txt"]
+        );
+        assert_eq!(
+            journal.undo(),
+            "Skipped \"Deleted 1 entries\", which cannot be undone. Undid \"Rename a.txt\""
+        );
+        assert!(Path::new(&a).exists());
+        assert_eq!(
+            journal.history(),
+            vec!["undone  Rename a.txt", "final   Deleted 1 entries"]
+        );
+        assert!(journal
+            .undo()
+            .starts_with("Cannot undo \"Deleted 1 entries\""));
+        assert_eq!(journal.done.len(), 1);
+
+        journal.record(
+            "Deleted 2 entries",
+            vec![
+                Operation::Delete {
+                    path: "/gone".to_owned(),
+                },
+                Operation::Rename {
+                    from: a.clone(),
+                    to: b.clone(),
+                },
+            ],
+        );
+        fs::rename(&a, &b).unwrap();
+        assert_eq!(
+            journal.undo(),
+            "Undid \"Deleted 2 entries\"; not restored, deleted permanently: /gone"
+        );
+        assert!(Path::new(&a).exists());
+        assert_eq!(journal.done.len(), 1);
+
+        journal.record("Nothing", Vec::new());
+        assert_eq!(journal.done.len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
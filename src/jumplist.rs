@@ -0,0 +1,101 @@
+const MAX_ENTRIES: usize = 100;
+
+/// Where the user was: a directory, the entry selected in it and the filter typed at the time.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Position {
+    pub dir: String,
+    pub selected: Option<String>,
+    pub filter: String,
+}
+
+/// Directories left by a move, walked with Ctrl+O and Ctrl+I like the vim jump list.
+/// `index` equals `entries.len()` until the user starts walking back.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JumpList {
+    entries: Vec<Position>,
+    index: usize,
+    pub previous: Option<Position>,
+}
+
+impl JumpList {
+    /// Records `from` as left behind by a regular move, dropping any forward history.
+    pub fn push(&mut self, from: Position) {
+        self.entries.truncate(self.index);
+        if self.entries.last().map(|last| &last.dir) != Some(&from.dir) {
+            self.entries.push(from.clone());
+        }
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.index = self.entries.len();
+        self.previous = Some(from);
+    }
+
+    pub fn back(&mut self, current: Position) -> Option<Position> {
+        if self.index == 0 {
+            return None;
+        }
+
+        if self.index == self.entries.len() {
+            self.entries.push(current.clone());
+        } else {
+            self.entries[self.index] = current.clone();
+        }
+        self.index -= 1;
+        self.previous = Some(current);
+        Some(self.entries[self.index].clone())
+    }
+
+    pub fn forward(&mut self, current: Position) -> Option<Position> {
+        if self.index + 1 >= self.entries.len() {
+            return None;
+        }
+
+        self.entries[self.index] = current.clone();
+        self.index += 1;
+        self.previous = Some(current);
+        Some(self.entries[self.index].clone())
+    }
+}
+
+#[cfg(test)]
+mod jumplist_tests {
+    use super::*;
+
+    fn at(dir: &str) -> Position {
+        Position {
+            dir: dir.to_owned(),
+            ..Position::default()
+        }
+    }
+
+    #[test]
+    fn test_back_and_forward() {
+        let mut jumps = JumpList::default();
+        assert_eq!(jumps.back(at("/a")), None);
+
+        jumps.push(at("/a"));
+        jumps.push(at("/b"));
+        assert_eq!(jumps.back(at("/c")), Some(at("/b")));
+        assert_eq!(jumps.back(at("/b")), Some(at("/a")));
+        assert_eq!(jumps.back(at("/a")), None);
+        assert_eq!(jumps.forward(at("/a")), Some(at("/b")));
+        assert_eq!(jumps.forward(at("/b")), Some(at("/c")));
+        assert_eq!(jumps.forward(at("/c")), None);
+
+        jumps.back(at("/c"));
+        jumps.push(at("/b"));
+        assert_eq!(jumps.forward(at("/d")), None);
+        assert_eq!(jumps.back(at("/d")), Some(at("/b")));
+        assert_eq!(jumps.previous, Some(at("/d")));
+    }
+
+    #[test]
+    fn test_push_skips_repeats() {
+        let mut jumps = JumpList::default();
+        jumps.push(at("/a"));
+        jumps.push(at("/a"));
+        assert_eq!(jumps.back(at("/b")), Some(at("/a")));
+        assert_eq!(jumps.back(at("/a")), None);
+    }
+}
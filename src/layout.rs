@@ -0,0 +1,314 @@
+use crossterm::terminal;
+use std::cmp;
+
+const ELLIPSIS: char = '…';
+const MIN_NAME_WIDTH: usize = 12;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Columns {
+    pub index: usize,
+    pub icon: usize,
+    pub name: usize,
+    pub details: usize,
+}
+
+/// Area available to the file list: everything between the header row and the message row.
+pub fn list_area() -> Rect {
+    let (width, height) = terminal::size().unwrap_or((80, 24));
+    Rect {
+        x: 0,
+        y: 1,
+        width,
+        height: height.saturating_sub(2),
+    }
+}
+
+/// Splits `area` into a left and a right pane, leaving one column between them for a
+/// separator.
+pub fn split_columns(area: Rect, left_percent: u16) -> (Rect, Rect) {
+    let left_width = (area.width as u32 * left_percent as u32 / 100) as u16;
+    let right_x = area.x + left_width + 1;
+    let left = Rect {
+        width: left_width,
+        ..area
+    };
+    let right = Rect {
+        x: right_x,
+        width: area.width.saturating_sub(left_width + 1),
+        ..area
+    };
+    (left, right)
+}
+
+/// Number of terminal cells a character occupies. Nerd font icons live in the private use
+/// areas and are rendered as a single cell.
+pub fn char_width(c: char) -> usize {
+    let code = c as u32;
+    match code {
+        0 | 0x01..=0x1f | 0x7f..=0x9f => 0,
+        0x0300..=0x036f
+        | 0x1ab0..=0x1aff
+        | 0x1dc0..=0x1dff
+        | 0x200b..=0x200f
+        | 0x20d0..=0x20ff
+        | 0xfe00..=0xfe0f
+        | 0xfe20..=0xfe2f => 0,
+        0x1100..=0x115f
+        | 0x2e80..=0x303e
+        | 0x3041..=0x33ff
+        | 0x3400..=0x4dbf
+        | 0x4e00..=0x9fff
+        | 0xa000..=0xa4cf
+        | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff
+        | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60
+        | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f
+        | 0x1f680..=0x1f6ff
+        | 0x1f900..=0x1f9ff
+        | 0x1fa70..=0x1faff
+        | 0x20000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+pub fn str_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
+
+/// Longest prefix of `s` that fits in `width` cells.
+pub fn take_width(s: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in s.char_indices() {
+        used += char_width(c);
+        if used > width {
+            return &s[..i];
+        }
+    }
+    s
+}
+
+/// Longest suffix of `s` that fits in `width` cells.
+fn take_width_rev(s: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in s.char_indices().rev() {
+        used += char_width(c);
+        if used > width {
+            return &s[i + c.len_utf8()..];
+        }
+    }
+    s
+}
+
+/// Splits `name` into stem and extension (including the dot). Dotfiles have no extension.
+pub fn split_extension(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(pos) if pos > 0 && pos < name.len() - 1 => name.split_at(pos),
+        _ => (name, ""),
+    }
+}
+
+fn ellipsize(s: &str, width: usize) -> String {
+    let budget = width.saturating_sub(1);
+    let tail = take_width_rev(s, budget / 2);
+    let head = take_width(s, budget - str_width(tail));
+    format!("{}{}{}", head, ELLIPSIS, tail)
+}
+
+/// Shortens `name` to at most `width` cells by replacing its middle with an ellipsis, keeping
+/// the extension intact whenever there is room for it.
+pub fn truncate_middle(name: &str, width: usize) -> String {
+    if str_width(name) <= width {
+        return name.to_owned();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let (stem, ext) = split_extension(name);
+    let ext_width = str_width(ext);
+    if !ext.is_empty() && ext_width + 2 <= width {
+        return format!("{}{}", ellipsize(stem, width - ext_width), ext);
+    }
+
+    ellipsize(name, width)
+}
+
+fn digits(n: usize) -> usize {
+    n.to_string().len()
+}
+
+/// Splits a row of `width` cells into index, icon, name and details columns. The details
+/// column is dropped first when the terminal is too narrow to fit a readable name.
+pub fn allocate_columns(
+    width: usize,
+    entry_count: usize,
+    icon_width: usize,
+    details_width: usize,
+) -> Columns {
+    let index = digits(entry_count.saturating_sub(1)) + 1;
+    let icon = icon_width;
+    let fixed = index + icon;
+    let remaining = width.saturating_sub(fixed);
+
+    let details = if details_width > 0 && remaining > MIN_NAME_WIDTH + details_width {
+        details_width + 1
+    } else {
+        0
+    };
+
+    Columns {
+        index,
+        icon,
+        name: remaining - details,
+        details,
+    }
+}
+
+/// First visible row so that `selected` stays inside a window of `rows` lines.
+pub fn scroll_offset(selected: usize, offset: usize, rows: usize) -> usize {
+    if rows == 0 || selected < offset {
+        return selected;
+    }
+    if selected >= offset + rows {
+        return selected + 1 - rows;
+    }
+    offset
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", size, UNITS[unit])
+}
+
+pub fn max_width<'a>(items: impl Iterator<Item = &'a str>) -> usize {
+    items.map(str_width).fold(0, cmp::max)
+}
+
+#[cfg(test)]
+mod layout_tests {
+    use super::*;
+
+    #[test]
+    fn test_str_width() {
+        assert_eq!(str_width("main.rs"), 7);
+        assert_eq!(str_width("日本語.txt"), 10);
+        assert_eq!(str_width("🦀.rs"), 5);
+        assert_eq!(str_width("e\u{301}"), 1);
+        assert_eq!(str_width("\u{e7a8} "), 2);
+    }
+
+    #[test]
+    fn test_truncate_middle() {
+        struct TestCase {
+            name: &'static str,
+            width: usize,
+            expected: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "short.rs",
+                width: 10,
+                expected: "short.rs",
+            },
+            TestCase {
+                name: "very_long_filename.rs",
+                width: 12,
+                expected: "very…name.rs",
+            },
+            TestCase {
+                name: "no_extension_at_all",
+                width: 9,
+                expected: "no_e…_all",
+            },
+            TestCase {
+                name: ".gitignore_backup",
+                width: 8,
+                expected: ".git…kup",
+            },
+            TestCase {
+                name: "日本語のファイル.txt",
+                width: 11,
+                expected: "日本…ル.txt",
+            },
+            TestCase {
+                name: "name.extremelylongext",
+                width: 6,
+                expected: "nam…xt",
+            },
+        ];
+
+        for test_case in test_cases {
+            let truncated = truncate_middle(test_case.name, test_case.width);
+            assert_eq!(truncated, test_case.expected);
+            assert!(str_width(&truncated) <= test_case.width);
+        }
+    }
+
+    #[test]
+    fn test_allocate_columns() {
+        let columns = allocate_columns(80, 120, 2, 6);
+        assert_eq!(
+            columns,
+            Columns {
+                index: 4,
+                icon: 2,
+                name: 67,
+                details: 7,
+            }
+        );
+
+        let narrow = allocate_columns(20, 5, 2, 6);
+        assert_eq!(narrow.details, 0);
+        assert_eq!(narrow.name, 16);
+    }
+
+    #[test]
+    fn test_split_columns() {
+        let area = Rect {
+            x: 0,
+            y: 1,
+            width: 81,
+            height: 20,
+        };
+        let (left, right) = split_columns(area, 50);
+        assert_eq!((left.x, left.width), (0, 40));
+        assert_eq!((right.x, right.width), (41, 40));
+        assert_eq!((left.y, right.height), (1, 20));
+    }
+
+    #[test]
+    fn test_scroll_offset() {
+        assert_eq!(scroll_offset(3, 0, 10), 0);
+        assert_eq!(scroll_offset(12, 0, 10), 3);
+        assert_eq!(scroll_offset(2, 5, 10), 2);
+        assert_eq!(scroll_offset(7, 5, 10), 5);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512B");
+        assert_eq!(format_size(2048), "2.0K");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0M");
+    }
+}
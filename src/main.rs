@@ -4,10 +4,30 @@ use std::time::Duration;
 
 use crossterm::event::{self, Event, KeyModifiers};
 
+mod config;
+mod deletion;
+mod dirstate;
 mod event_handler;
 mod file;
+mod finder;
+mod frecency;
+mod grep;
+mod highlight;
+mod index;
+mod journal;
+mod jumplist;
+mod layout;
+mod marks;
+mod preview;
+mod register;
+mod rename;
+mod search;
+mod selection;
+mod session;
 mod state_handler;
 mod styles;
+mod trash;
+mod tree;
 
 fn start_slingshot(starting_state: &state_handler::AppState) -> Result<(), Box<dyn Error>> {
     let polling_interval = Duration::from_millis(10);
@@ -17,7 +37,10 @@ fn start_slingshot(starting_state: &state_handler::AppState) -> Result<(), Box<d
     loop {
         if event::poll(polling_interval)? {
             if let Event::Key(key_event) = event::read()? {
-                if key_event.modifiers != KeyModifiers::NONE {
+                if key_event
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                {
                     event_handler::handle_key_modifier(
                         key_event.code,
                         key_event.modifiers,
@@ -29,11 +52,60 @@ fn start_slingshot(starting_state: &state_handler::AppState) -> Result<(), Box<d
                     app_state.display()?;
                 }
             }
+        } else if app_state.poll_background() {
+            app_state.display()?;
         }
     }
 }
 
+struct Options {
+    restore: bool,
+    session: Option<String>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, Box<dyn Error>> {
+    let mut options = Options {
+        restore: false,
+        session: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--restore" => options.restore = true,
+            "--session" => match args.next() {
+                Some(name) => options.session = Some(name.to_owned()),
+                None => return Err("--session needs a name".into()),
+            },
+            _ => return Err(format!("Unknown argument: {}", arg).into()),
+        }
+    }
+    Ok(options)
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match args.first().map(|arg| arg.as_str()) {
+        Some("index") => Some(index::run_index_command()),
+        Some("query") => Some(frecency::run_query_command(&args[1..])),
+        _ => None,
+    };
+    if let Some(result) = command {
+        if let Err(e) = result {
+            eprintln!("slingshot: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("slingshot: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     panic::set_hook(Box::new(|panic_info| {
         crossterm::terminal::disable_raw_mode().expect("Failed to disable raw mode.");
         println!("Panic occurred: {:?}", panic_info);
@@ -41,9 +113,23 @@ fn main() {
 
     crossterm::terminal::enable_raw_mode().expect("Could not enable raw mode");
 
-    let initial_app_state =
+    let mut initial_app_state =
         state_handler::initial_app_state().expect("Error creating initial state");
 
+    // A named session is resumed whenever it exists; the default one only on request.
+    if let Some(name) = options.session {
+        initial_app_state.session_name = name;
+    }
+    let name = initial_app_state.session_name.clone();
+    let restore = options.restore || initial_app_state.config.restore_session;
+    match session::Session::load(&name) {
+        Ok(session) if restore || name != session::DEFAULT_NAME => {
+            initial_app_state.apply_session(&session)
+        }
+        Err(e) if options.restore => initial_app_state.message = e.to_string(),
+        _ => {}
+    }
+
     start_slingshot(&initial_app_state).unwrap();
 
     crossterm::terminal::disable_raw_mode().expect("Failed to disable raw mode.");
@@ -0,0 +1,158 @@
+use crate::config;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Named positions set with `m{a-z}`/`m{A-Z}`. Lowercase marks only last for the session,
+/// uppercase marks are stored one `name\tpath` line per mark in
+/// `$XDG_DATA_HOME/slingshot/marks`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Marks {
+    pub path: Option<PathBuf>,
+    session: BTreeMap<char, String>,
+    persistent: BTreeMap<char, String>,
+}
+
+pub fn marks_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("marks"))
+}
+
+pub fn is_valid_name(name: char) -> bool {
+    name.is_ascii_alphabetic()
+}
+
+impl Marks {
+    pub fn load(path: &Path) -> Marks {
+        let persistent = fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let (name, target) = line.split_once('\t')?;
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(name), None) if name.is_ascii_uppercase() => {
+                        Some((name, target.to_owned()))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+
+        Marks {
+            path: Some(path.to_owned()),
+            session: BTreeMap::new(),
+            persistent,
+        }
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let contents: String = self
+            .persistent
+            .iter()
+            .map(|(name, target)| format!("{}\t{}\n", name, target))
+            .collect();
+        let tmp = path.with_extension("tmp");
+        fs::File::create(&tmp)?.write_all(contents.as_bytes())?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: char) -> Option<&String> {
+        if name.is_ascii_uppercase() {
+            self.persistent.get(&name)
+        } else {
+            self.session.get(&name)
+        }
+    }
+
+    pub fn set(&mut self, name: char, target: &str) -> Result<(), Box<dyn Error>> {
+        if name.is_ascii_uppercase() {
+            self.persistent.insert(name, target.to_owned());
+            self.save()
+        } else {
+            self.session.insert(name, target.to_owned());
+            Ok(())
+        }
+    }
+
+    pub fn remove(&mut self, name: char) -> Result<(), Box<dyn Error>> {
+        if name.is_ascii_uppercase() {
+            self.persistent.remove(&name);
+            self.save()
+        } else {
+            self.session.remove(&name);
+            Ok(())
+        }
+    }
+
+    /// Removes every mark whose target no longer exists, returning how many were removed.
+    pub fn remove_missing(&mut self) -> Result<usize, Box<dyn Error>> {
+        let missing: Vec<char> = self
+            .list()
+            .into_iter()
+            .filter(|(_, target)| !Path::new(target).exists())
+            .map(|(name, _)| name)
+            .collect();
+
+        for name in &missing {
+            self.remove(*name)?;
+        }
+        Ok(missing.len())
+    }
+
+    /// All marks, lowercase before uppercase.
+    pub fn list(&self) -> Vec<(char, String)> {
+        self.session
+            .iter()
+            .chain(self.persistent.iter())
+            .map(|(name, target)| (*name, target.clone()))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.session.is_empty() && self.persistent.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod marks_tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_persist() {
+        let path = std::env::temp_dir().join(format!("slingshot_marks_{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let root = env!("CARGO_MANIFEST_DIR");
+
+        let mut marks = Marks::load(&path);
+        marks.set('a', root).unwrap();
+        marks.set('P', root).unwrap();
+        marks.set('M', "/does/not/exist").unwrap();
+        assert_eq!(marks.get('a'), Some(&root.to_owned()));
+        assert_eq!(marks.get('A'), None);
+
+        let reloaded = Marks::load(&path);
+        assert_eq!(reloaded.get('a'), None);
+        assert_eq!(reloaded.get('P'), Some(&root.to_owned()));
+        assert_eq!(reloaded.list().len(), 2);
+
+        assert_eq!(marks.remove_missing().unwrap(), 1);
+        assert_eq!(
+            marks.list(),
+            vec![('a', root.to_owned()), ('P', root.to_owned())]
+        );
+        assert_eq!(Marks::load(&path).list(), vec![('P', root.to_owned())]);
+
+        fs::remove_file(path).unwrap();
+    }
+}
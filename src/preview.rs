@@ -0,0 +1,316 @@
+use crate::file;
+use crate::highlight;
+use crate::layout;
+use crate::styles;
+use crossterm::style::{ResetColor, SetForegroundColor};
+use crossterm::{cursor, QueueableCommand};
+use std::borrow::Cow;
+use std::fs;
+use std::io::Read;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const PREVIEW_BYTES: u64 = 64 * 1024;
+const HEX_BYTES: u64 = 4 * 1024;
+const MAX_LINES: usize = 500;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Preview {
+    Text(Vec<highlight::Line>),
+    Directory(Vec<file::FileData>),
+    Binary { size: u64, kind: &'static str },
+    Hex(Vec<String>),
+    Error(String),
+}
+
+/// Loads previews on a background thread. Only the result of the latest request is kept, so
+/// scrolling through the list never waits on the file system.
+#[derive(Clone, Default)]
+pub struct Previewer {
+    pub hex: bool,
+    pub current: Option<Preview>,
+    requested: Option<(String, bool)>,
+    generation: Arc<AtomicUsize>,
+    slot: Arc<Mutex<Option<(usize, Preview)>>>,
+}
+
+impl Previewer {
+    pub fn request(&mut self, path: &str) {
+        let key = (path.to_owned(), self.hex);
+        if self.requested.as_ref() == Some(&key) {
+            return;
+        }
+
+        self.requested = Some(key);
+        self.current = None;
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let latest = Arc::clone(&self.generation);
+        let slot = Arc::clone(&self.slot);
+        let path = path.to_owned();
+        let hex = self.hex;
+
+        thread::spawn(move || {
+            let preview = load_preview(&path, hex);
+            if latest.load(Ordering::SeqCst) == generation {
+                if let Ok(mut slot) = slot.lock() {
+                    *slot = Some((generation, preview));
+                }
+            }
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.requested = None;
+        self.current = None;
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn toggle_hex(&mut self) {
+        self.hex = !self.hex;
+        self.requested = None;
+    }
+
+    /// Picks up a finished preview. Returns true when the display needs to be refreshed.
+    pub fn poll(&mut self) -> bool {
+        let finished = match self.slot.lock() {
+            Ok(mut slot) => slot.take(),
+            Err(_) => None,
+        };
+
+        match finished {
+            Some((generation, preview)) if generation == self.generation.load(Ordering::SeqCst) => {
+                self.current = Some(preview);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn read_head(path: &str, limit: u64) -> std::io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    fs::File::open(path)?.take(limit).read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn detect_kind(bytes: &[u8]) -> &'static str {
+    const MAGIC: [(&[u8], &str); 9] = [
+        (b"\x89PNG", "PNG image"),
+        (b"\xff\xd8\xff", "JPEG image"),
+        (b"GIF8", "GIF image"),
+        (b"%PDF", "PDF document"),
+        (b"\x7fELF", "ELF executable"),
+        (b"PK\x03\x04", "ZIP archive"),
+        (b"\x1f\x8b", "gzip archive"),
+        (b"ID3", "MP3 audio"),
+        (b"RIFF", "RIFF media"),
+    ];
+
+    MAGIC
+        .iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+        .map(|(_, kind)| *kind)
+        .unwrap_or("binary data")
+}
+
+pub fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!("{:08x}  {:<47}  |{}|", i * 16, hex.join(" "), ascii)
+        })
+        .collect()
+}
+
+pub fn load_preview(path: &str, hex: bool) -> Preview {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => return Preview::Error(e.to_string()),
+    };
+
+    if metadata.is_dir() {
+        return match fs::read_dir(path) {
+            Ok(paths) => match file::generate_file_data(paths) {
+                Ok(children) => Preview::Directory(children),
+                Err(e) => Preview::Error(e.to_string()),
+            },
+            Err(e) => Preview::Error(e.to_string()),
+        };
+    }
+
+    let limit = if hex { HEX_BYTES } else { PREVIEW_BYTES };
+    let bytes = match read_head(path, limit) {
+        Ok(bytes) => bytes,
+        Err(e) => return Preview::Error(e.to_string()),
+    };
+
+    if hex {
+        return Preview::Hex(hex_dump(&bytes));
+    }
+
+    if bytes.contains(&0) {
+        return Preview::Binary {
+            size: metadata.len(),
+            kind: detect_kind(&bytes),
+        };
+    }
+
+    let lines: Vec<String> = String::from_utf8_lossy(&bytes)
+        .lines()
+        .take(MAX_LINES)
+        .map(|line| line.replace('\t', "    "))
+        .collect();
+    Preview::Text(highlight::highlight(&lines, highlight::syntax_for(path)))
+}
+
+fn print_lines(lines: &[String], area: layout::Rect, stdout: &mut std::io::Stdout) {
+    for (row, line) in lines.iter().take(area.height as usize).enumerate() {
+        stdout
+            .queue(cursor::MoveTo(area.x, area.y + row as u16))
+            .unwrap();
+        print!("{}", layout::take_width(line, area.width as usize));
+    }
+}
+
+fn print_spans(line: &highlight::Line, width: usize) {
+    let mut remaining = width;
+    for (kind, text) in line {
+        let clipped = layout::take_width(text, remaining);
+        print!("{}{}", SetForegroundColor(kind.color()), clipped);
+        remaining -= layout::str_width(clipped);
+        if clipped.len() < text.len() {
+            break;
+        }
+    }
+    print!("{}", ResetColor);
+}
+
+pub fn print_preview(preview: Option<&Preview>, area: layout::Rect, stdout: &mut std::io::Stdout) {
+    let width = area.width as usize;
+    match preview {
+        None => {
+            stdout.queue(cursor::MoveTo(area.x, area.y)).unwrap();
+            print!(
+                "{}{}{}",
+                SetForegroundColor(styles::LIGHT_CONTRAST),
+                layout::take_width("Loading…", width),
+                ResetColor
+            );
+        }
+        Some(Preview::Text(lines)) => {
+            let gutter = lines.len().to_string().len() + 1;
+            for (row, line) in lines.iter().take(area.height as usize).enumerate() {
+                let y = area.y + row as u16;
+                stdout.queue(cursor::MoveTo(area.x, y)).unwrap();
+                print!(
+                    "{}{:>w$}{}",
+                    SetForegroundColor(styles::LIGHT_CONTRAST),
+                    row + 1,
+                    ResetColor,
+                    w = gutter - 1
+                );
+                stdout
+                    .queue(cursor::MoveTo(area.x + gutter as u16, y))
+                    .unwrap();
+                print_spans(line, width.saturating_sub(gutter));
+            }
+        }
+        Some(Preview::Directory(children)) => {
+            file::print_file_data(
+                Cow::Borrowed(children),
+                usize::MAX,
+                0,
+                file::Decorations::default(),
+                area,
+                stdout,
+            );
+        }
+        Some(Preview::Binary { size, kind }) => {
+            let lines = vec![
+                format!("{}", kind),
+                format!("{} ({} bytes)", layout::format_size(*size), size),
+                "Press b for a hex dump".to_owned(),
+            ];
+            print_lines(&lines, area, stdout);
+        }
+        Some(Preview::Hex(lines)) => print_lines(lines, area, stdout),
+        Some(Preview::Error(e)) => {
+            stdout.queue(cursor::MoveTo(area.x, area.y)).unwrap();
+            print!(
+                "{}{}{}",
+                SetForegroundColor(styles::ERR),
+                layout::take_width(e, width),
+                ResetColor
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod preview_tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn write_temp_file(name: &str, contents: &[u8]) -> String {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("slingshot_{}_{}", std::process::id(), name));
+        fs::write(&path, contents).expect("Could not write temp file");
+        path.to_str()
+            .expect("Could not turn path to str")
+            .to_owned()
+    }
+
+    #[test]
+    fn test_load_preview() {
+        let text = write_temp_file("preview.txt", b"first\n\tsecond\n");
+        assert_eq!(
+            load_preview(&text, false),
+            Preview::Text(vec![
+                vec![(highlight::TokenKind::Plain, "first".to_owned())],
+                vec![(highlight::TokenKind::Plain, "    second".to_owned())],
+            ])
+        );
+
+        let binary = write_temp_file("preview.png", b"\x89PNG\r\n\x1a\n\0\0");
+        assert_eq!(
+            load_preview(&binary, false),
+            Preview::Binary {
+                size: 10,
+                kind: "PNG image"
+            }
+        );
+
+        let dir = format!("{}/tests/dir1", env!("CARGO_MANIFEST_DIR"));
+        match load_preview(&dir, false) {
+            Preview::Directory(children) => assert_eq!(children.len(), 3),
+            other => panic!("Expected directory preview, got {:?}", other),
+        }
+
+        fs::remove_file(text).unwrap();
+        fs::remove_file(binary).unwrap();
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let lines = hex_dump(b"slingshot\x00\x01 hex dump test");
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "00000000  73 6c 69 6e 67 73 68 6f 74 00 01 20 68 65 78 20  |slingshot.. hex |"
+        );
+        assert!(lines[1].starts_with("00000010  64 75 6d 70"));
+    }
+}
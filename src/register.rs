@@ -0,0 +1,283 @@
+use crate::deletion;
+use crate::file;
+use crate::journal;
+use crate::trash;
+use std::fs;
+use std::io;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+/// Paths yanked (`yy`, `y`) or cut (`dd`, `x`), waiting to be pasted. Paths are absolute, so
+/// the register survives changing directories.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Register {
+    pub paths: Vec<String>,
+    pub cut: bool,
+}
+
+impl Register {
+    pub fn store(&mut self, paths: Vec<String>, cut: bool) {
+        self.paths = paths;
+        self.cut = cut;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+}
+
+/// What to do with a pasted entry whose name is taken in the target directory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Conflict {
+    /// Moves the existing entry to the trash first, so that undo can bring it back.
+    Overwrite,
+    Skip,
+    /// Pastes as `name (1).ext`.
+    Rename,
+}
+
+/// A paste in progress. It stops at every name that is taken until a `Conflict` is picked,
+/// unless one was picked for all of them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Paste {
+    pub dir: String,
+    pub pending: Vec<String>,
+    pub cut: bool,
+    pub for_all: Option<Conflict>,
+    pub operations: Vec<journal::Operation>,
+    pub pasted: usize,
+    pub failures: Vec<String>,
+}
+
+/// Copies `from` to `to`, with everything below it when it is a directory. Symlinks are copied
+/// as symlinks rather than as what they point to.
+pub fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    let metadata = from.symlink_metadata()?;
+    if metadata.file_type().is_symlink() {
+        return symlink(fs::read_link(from)?, to);
+    }
+    if !metadata.is_dir() {
+        return fs::copy(from, to).map(|_| ());
+    }
+
+    fs::create_dir(to)?;
+    fs::set_permissions(to, metadata.permissions())?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// Like `copy_recursive`, but a copy that fails part way is deleted again rather than left
+/// behind half written.
+fn copy_complete(from: &Path, to: &Path) -> io::Result<()> {
+    if to.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        ));
+    }
+    copy_recursive(from, to).inspect_err(|_| {
+        deletion::remove_all(to);
+    })
+}
+
+/// Moves `from` to `to`. Across filesystems, where renaming is not possible, `from` is copied
+/// and deleted once the copy is complete.
+pub fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_complete(from, to)?;
+            match deletion::remove_all(from).first() {
+                Some((path, error)) => Err(io::Error::other(format!(
+                    "copied, but could not delete {}: {}",
+                    path, error
+                ))),
+                None => Ok(()),
+            }
+        }
+        result => result,
+    }
+}
+
+fn to_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+impl Paste {
+    pub fn new(register: &Register, dir: &str) -> Paste {
+        Paste {
+            dir: dir.to_owned(),
+            pending: register.paths.clone(),
+            cut: register.cut,
+            ..Paste::default()
+        }
+    }
+
+    /// Pastes the pending entries in order. Returns the name of the first one that is taken,
+    /// which stays pending until `resume` is called again with a `choice` for it.
+    pub fn resume(&mut self, mut choice: Option<Conflict>) -> Option<String> {
+        while let Some(source) = self.pending.first().cloned() {
+            let source_path = Path::new(&source);
+            let name = match source_path.file_name() {
+                Some(name) => name.to_owned(),
+                None => {
+                    self.pending.remove(0);
+                    continue;
+                }
+            };
+            let mut target = Path::new(&self.dir).join(&name);
+
+            if target.starts_with(source_path) && target != source_path {
+                self.pending.remove(0);
+                self.failures
+                    .push(format!("{}: cannot paste a directory into itself", source));
+                continue;
+            }
+            if target.symlink_metadata().is_ok() {
+                // Moving an entry onto itself changes nothing, copying it makes a numbered copy.
+                let conflict = if target == source_path && self.cut {
+                    Conflict::Skip
+                } else if target == source_path {
+                    Conflict::Rename
+                } else {
+                    match choice.take().or(self.for_all) {
+                        Some(conflict) => conflict,
+                        None => return Some(name.to_string_lossy().into_owned()),
+                    }
+                };
+
+                match conflict {
+                    Conflict::Skip => {
+                        self.pending.remove(0);
+                        continue;
+                    }
+                    Conflict::Rename => target = file::unique_path(&target),
+                    Conflict::Overwrite => match trash::trash_path(&to_string(&target)) {
+                        Ok(trashed) => self.operations.push(journal::Operation::Trash {
+                            original: to_string(&target),
+                            trashed: to_string(&trashed),
+                        }),
+                        Err(e) => {
+                            self.pending.remove(0);
+                            self.failures.push(format!("{}: {}", to_string(&target), e));
+                            continue;
+                        }
+                    },
+                }
+            }
+
+            self.pending.remove(0);
+            let result = if self.cut {
+                move_path(source_path, &target)
+            } else {
+                copy_complete(source_path, &target)
+            };
+            match result {
+                Ok(()) if self.cut => self.operations.push(journal::Operation::Rename {
+                    from: source,
+                    to: to_string(&target),
+                }),
+                Ok(()) => self.operations.push(journal::Operation::Create {
+                    path: to_string(&target),
+                    trashed: None,
+                }),
+                Err(e) => {
+                    self.failures.push(format!("{}: {}", source, e));
+                    continue;
+                }
+            }
+            self.pasted += 1;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod register_tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_copy_and_move() {
+        let root = std::env::temp_dir().join(format!("slingshot_register_{}", std::process::id()));
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::write(root.join("src/nested/file.txt"), "abc").unwrap();
+        symlink("nested/file.txt", root.join("src/link")).unwrap();
+
+        copy_recursive(&root.join("src"), &root.join("copy")).unwrap();
+        assert_eq!(
+            fs::read_to_string(root.join("copy/nested/file.txt")).unwrap(),
+            "abc"
+        );
+        assert!(fs::symlink_metadata(root.join("copy/link"))
+            .unwrap()
+            .file_type()
+            .is_symlink());
+
+        fs::create_dir(root.join("locked")).unwrap();
+        fs::write(root.join("locked/secret"), "").unwrap();
+        fs::set_permissions(
+            root.join("locked/secret"),
+            fs::Permissions::from_mode(0o000),
+        )
+        .unwrap();
+        if fs::read(root.join("locked/secret")).is_err() {
+            assert!(copy_complete(&root.join("locked"), &root.join("partial")).is_err());
+            assert!(!root.join("partial").exists());
+        }
+        assert!(copy_complete(&root.join("src"), &root.join("copy")).is_err());
+
+        move_path(&root.join("copy"), &root.join("moved")).unwrap();
+        assert!(!root.join("copy").exists());
+        assert!(root.join("moved/nested/file.txt").exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_paste_conflicts() {
+        let root = std::env::temp_dir().join(format!("slingshot_paste_{}", std::process::id()));
+        let dest = root.join("dest");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(root.join("a.txt"), "new").unwrap();
+        fs::write(root.join("b.txt"), "new").unwrap();
+        fs::write(dest.join("a.txt"), "old").unwrap();
+        fs::write(dest.join("b.txt"), "old").unwrap();
+
+        let mut register = Register::default();
+        register.store(
+            vec![
+                to_string(&root.join("a.txt")),
+                to_string(&root.join("b.txt")),
+            ],
+            false,
+        );
+        let mut paste = Paste::new(&register, &to_string(&dest));
+        assert_eq!(paste.resume(None), Some("a.txt".to_owned()));
+        assert_eq!(paste.pending.len(), 2);
+        assert_eq!(paste.resume(Some(Conflict::Skip)), Some("b.txt".to_owned()));
+        assert_eq!(paste.resume(Some(Conflict::Rename)), None);
+        assert_eq!(paste.pasted, 1);
+        assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(dest.join("b (1).txt")).unwrap(), "new");
+
+        register.store(vec![to_string(&root.join("a.txt")), to_string(&dest)], true);
+        let mut paste = Paste::new(&register, &to_string(&dest));
+        paste.for_all = Some(Conflict::Rename);
+        assert_eq!(paste.resume(None), None);
+        assert_eq!(paste.failures.len(), 1);
+        assert!(!root.join("a.txt").exists());
+        assert_eq!(fs::read_to_string(dest.join("a (1).txt")).unwrap(), "new");
+        assert_eq!(
+            paste.operations,
+            vec![journal::Operation::Rename {
+                from: to_string(&root.join("a.txt")),
+                to: to_string(&dest.join("a (1).txt")),
+            }]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
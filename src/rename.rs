@@ -0,0 +1,330 @@
+use crate::config;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::path::Path;
+
+/// A single line of editable text with a cursor, used to edit names in place.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LineEditor {
+    chars: Vec<char>,
+    /// Position of the cursor in `chars`.
+    pub cursor: usize,
+}
+
+impl LineEditor {
+    /// Starts editing `text` with the cursor at its end.
+    pub fn new(text: &str) -> LineEditor {
+        let chars: Vec<char> = text.chars().collect();
+        LineEditor {
+            cursor: chars.len(),
+            chars,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    /// The text left of the cursor, to place the terminal cursor.
+    pub fn before_cursor(&self) -> String {
+        self.chars[..self.cursor].iter().collect()
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.chars.len());
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.chars.len();
+    }
+}
+
+/// An entry being renamed in place. With `cw` only the part before the extension is edited and
+/// `suffix` holds the extension, which is kept as it is.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InlineRename {
+    pub target: String,
+    pub editor: LineEditor,
+    pub suffix: String,
+}
+
+impl InlineRename {
+    pub fn new_name(&self) -> String {
+        format!("{}{}", self.editor.text(), self.suffix)
+    }
+}
+
+/// Checks that `name` is a single, non-empty path component.
+fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(format!("Invalid name: \"{}\"", name));
+    }
+    if name.contains(['/', '\0']) {
+        return Err(format!("Names cannot contain '/': {}", name));
+    }
+    Ok(())
+}
+
+/// Checks that `name` can replace `original` in `dir`: it must be a single, non-empty path
+/// component that is not taken yet.
+pub fn validate_name(dir: &Path, original: &str, name: &str) -> Result<(), String> {
+    check_name(name)?;
+    if name == original {
+        return Ok(());
+    }
+    // Where names are case-insensitive, `A` already "exists" when renaming `a` to it.
+    let existing = dir.join(name).symlink_metadata();
+    let same_file = |existing: &fs::Metadata| {
+        dir.join(original).symlink_metadata().is_ok_and(|metadata| {
+            (metadata.dev(), metadata.ino()) == (existing.dev(), existing.ino())
+        })
+    };
+    match existing {
+        Ok(existing) if !same_file(&existing) => Err(format!("{} already exists", name)),
+        _ => Ok(()),
+    }
+}
+
+/// Renames of many entries at once, edited as a list of names in the external editor.
+/// `steps` is the order to carry them out in, going through temporary names where entries
+/// swap names or rename in a cycle.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BulkRename {
+    pub renames: Vec<(String, String)>,
+    pub steps: Vec<(String, String)>,
+}
+
+/// Contents of the file edited for a bulk rename: the name of every entry, one per line.
+pub fn names_file(paths: &[String]) -> String {
+    paths.iter().map(|path| file_name(path) + "\n").collect()
+}
+
+/// Writes the names file to `file`, which must not exist yet. The file is only readable by the
+/// user, and an existing file or symlink in its place is never written through.
+pub fn write_names_file(file: &Path, paths: &[String]) -> io::Result<()> {
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(file)?
+        .write_all(names_file(paths).as_bytes())
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn parent(path: &str) -> &Path {
+    Path::new(path).parent().unwrap_or(Path::new("/"))
+}
+
+/// A name for moving `path` out of the way that is neither taken nor one of the `targets`.
+fn temporary_name(path: &str, targets: &HashSet<&str>) -> String {
+    (0..)
+        .map(|n| {
+            let name = format!(".{}.slingshot-rename-{}", file_name(path), n);
+            parent(path).join(name).to_string_lossy().into_owned()
+        })
+        .find(|candidate| {
+            !targets.contains(candidate.as_str())
+                && Path::new(candidate).symlink_metadata().is_err()
+        })
+        .unwrap_or_default()
+}
+
+impl BulkRename {
+    /// Compares the edited names with the names of `paths`, line by line. Every entry stays in
+    /// its directory, so names cannot contain `/`. Two entries ending up with the same name, or
+    /// a name taken by an entry that is not renamed itself, refuse the whole rename.
+    pub fn plan(paths: &[String], edited: &str) -> Result<BulkRename, String> {
+        let names: Vec<&str> = edited.lines().collect();
+        if names.len() != paths.len() {
+            return Err(format!(
+                "Expected {} names but got {}; lines cannot be added or removed",
+                paths.len(),
+                names.len()
+            ));
+        }
+
+        let mut targets: HashMap<String, &str> = HashMap::new();
+        let mut renames = Vec::new();
+        for (path, name) in paths.iter().zip(names) {
+            let original = file_name(path);
+            check_name(name)?;
+            let target = parent(path).join(name).to_string_lossy().into_owned();
+            if let Some(other) = targets.insert(target.clone(), path) {
+                return Err(format!(
+                    "{} and {} would both be named {}",
+                    config::abbreviate_home(other),
+                    config::abbreviate_home(path),
+                    name
+                ));
+            }
+            if name != original {
+                renames.push((path.clone(), target));
+            }
+        }
+
+        let sources: HashSet<&str> = renames.iter().map(|(from, _)| from.as_str()).collect();
+        for (from, to) in &renames {
+            let existing = Path::new(to).symlink_metadata();
+            if existing.is_ok() && !sources.contains(to.as_str()) {
+                validate_name(parent(from), &file_name(from), &file_name(to))?;
+            }
+        }
+
+        let steps = BulkRename::order(&renames);
+        Ok(BulkRename { renames, steps })
+    }
+
+    /// Orders the renames so that no entry is renamed onto one that has yet to move. Cycles
+    /// (including swaps) are broken by moving one entry to a temporary name first.
+    fn order(renames: &[(String, String)]) -> Vec<(String, String)> {
+        let targets: HashSet<&str> = renames.iter().map(|(_, to)| to.as_str()).collect();
+        let mut pending: Vec<(String, String)> = renames.to_vec();
+        let mut steps = Vec::new();
+
+        while !pending.is_empty() {
+            let ready = pending
+                .iter()
+                .position(|(_, to)| !pending.iter().any(|(from, _)| from == to));
+            match ready {
+                Some(index) => steps.push(pending.remove(index)),
+                None => {
+                    let temporary = temporary_name(&pending[0].0, &targets);
+                    let from = std::mem::replace(&mut pending[0].0, temporary.clone());
+                    steps.push((from, temporary));
+                }
+            }
+        }
+        steps
+    }
+
+    /// One `old -> new` line per renamed entry, for the preview.
+    pub fn lines(&self) -> Vec<String> {
+        self.renames
+            .iter()
+            .map(|(from, to)| format!("{} -> {}", config::abbreviate_home(from), file_name(to)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod rename_tests {
+    use super::*;
+
+    #[test]
+    fn test_line_editor() {
+        let mut editor = LineEditor::new("main.rs");
+        editor.home();
+        editor.delete();
+        editor.insert('M');
+        editor.end();
+        editor.backspace();
+        editor.left();
+        editor.insert('x');
+        editor.right();
+        editor.right();
+        editor.insert('!');
+        assert_eq!(editor.text(), "Main.xr!");
+        assert_eq!(editor.before_cursor(), "Main.xr!");
+    }
+
+    #[test]
+    fn test_validate_name() {
+        let dir = std::env::temp_dir().join(format!("slingshot_rename_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("taken.txt"), "").unwrap();
+
+        assert!(validate_name(&dir, "a.txt", "b.txt").is_ok());
+        assert!(validate_name(&dir, "a.txt", "A.txt").is_ok());
+        assert!(validate_name(&dir, "a.txt", "taken.txt").is_err());
+        assert!(validate_name(&dir, "a.txt", "sub/b.txt").is_err());
+        assert!(validate_name(&dir, "a.txt", "").is_err());
+        assert!(validate_name(&dir, "a.txt", "..").is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_bulk_rename_plan() {
+        let dir = std::env::temp_dir().join(format!("slingshot_bulk_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("other.txt"), "").unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        let paths = vec![path("a"), path("b"), path("c")];
+        assert_eq!(names_file(&paths), "a\nb\nc\n");
+
+        let names = dir.join("names.txt");
+        write_names_file(&names, &paths).unwrap();
+        assert_eq!(fs::read_to_string(&names).unwrap(), "a\nb\nc\n");
+        assert!(write_names_file(&names, &paths).is_err());
+        std::os::unix::fs::symlink(dir.join("other.txt"), dir.join("link.txt")).unwrap();
+        assert!(write_names_file(&dir.join("link.txt"), &paths).is_err());
+        assert_eq!(fs::read_to_string(dir.join("other.txt")).unwrap(), "");
+
+        let plan = BulkRename::plan(&paths, "a\nc\nd\n").unwrap();
+        assert_eq!(
+            plan.renames,
+            vec![(path("b"), path("c")), (path("c"), path("d"))]
+        );
+        assert_eq!(
+            plan.steps,
+            vec![(path("c"), path("d")), (path("b"), path("c"))]
+        );
+        assert_eq!(plan.lines().len(), 2);
+
+        let swap = BulkRename::plan(&paths, "b\na\nc").unwrap();
+        let temporary = path(".a.slingshot-rename-0");
+        assert_eq!(
+            swap.steps,
+            vec![
+                (path("a"), temporary.clone()),
+                (path("b"), path("a")),
+                (temporary, path("b")),
+            ]
+        );
+
+        assert!(BulkRename::plan(&paths, "a\nb").is_err());
+        assert!(BulkRename::plan(&paths, "x\nx\nc").is_err());
+        assert!(BulkRename::plan(&paths, "a\nb\nc")
+            .unwrap()
+            .renames
+            .is_empty());
+        assert!(BulkRename::plan(&paths, "a\nb\nsub/c").is_err());
+        assert!(BulkRename::plan(&paths, "a\nb\nother.txt").is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,171 @@
+use crate::file::FileData;
+use crate::grep::Pattern;
+use crate::layout;
+
+const MAX_HISTORY: usize = 100;
+
+/// State of `/` and `?`: the pattern being typed, the last confirmed pattern used by `n` and
+/// `N`, and previously confirmed patterns. The prompt is also used to select entries by
+/// pattern, in which case `select` is set.
+#[derive(Clone, Debug, Default)]
+pub struct Search {
+    pub input: String,
+    pub pattern: Option<Pattern>,
+    pub forward: bool,
+    pub origin: usize,
+    pub select: bool,
+    history: Vec<String>,
+    history_index: Option<usize>,
+}
+
+/// Index of the first entry after (or before) `from` whose name contains `pattern`, wrapping
+/// around the ends of the list. `from` itself is only checked last.
+pub fn find_match(
+    paths: &[FileData],
+    pattern: &Pattern,
+    from: usize,
+    forward: bool,
+) -> Option<usize> {
+    find_entry(paths, from, forward, |fd| {
+        !pattern.find_all(&fd.shortname).is_empty()
+    })
+}
+
+/// Like `find_match`, for the next entry with the same extension as the one at `from`.
+pub fn find_same_extension(paths: &[FileData], from: usize, forward: bool) -> Option<usize> {
+    let extension = match paths.get(from) {
+        Some(fd) if !fd.is_dir => layout::split_extension(&fd.shortname).1,
+        _ => return None,
+    };
+    if extension.is_empty() {
+        return None;
+    }
+    find_entry(paths, from, forward, |fd| {
+        !fd.is_dir && layout::split_extension(&fd.shortname).1 == extension
+    })
+}
+
+fn find_entry(
+    paths: &[FileData],
+    from: usize,
+    forward: bool,
+    matches: impl Fn(&FileData) -> bool,
+) -> Option<usize> {
+    let len = paths.len();
+    (1..=len)
+        .map(|step| {
+            if forward {
+                (from + step) % len
+            } else {
+                (from + len * step - step) % len
+            }
+        })
+        .find(|i| matches(&paths[*i]))
+}
+
+impl Search {
+    pub fn start(&mut self, forward: bool, origin: usize) {
+        self.input = String::new();
+        self.forward = forward;
+        self.origin = origin;
+        self.select = false;
+        self.history_index = None;
+    }
+
+    /// The pattern to highlight: the one being typed while the prompt is open, otherwise the
+    /// last confirmed one.
+    pub fn highlight(&self, prompt_open: bool) -> Option<Pattern> {
+        if prompt_open {
+            Some(Pattern::new(&self.input)).filter(|_| !self.input.is_empty())
+        } else {
+            self.pattern.clone()
+        }
+    }
+
+    pub fn confirm(&mut self) {
+        if self.input.is_empty() {
+            return;
+        }
+        self.history.retain(|entry| entry != &self.input);
+        self.history.push(self.input.clone());
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.pattern = Some(Pattern::new(&self.input));
+    }
+
+    /// Replaces the input with an older (`older == true`) or newer history entry.
+    pub fn browse_history(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let index = match (self.history_index, older) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => None,
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) if i + 1 < self.history.len() => Some(i + 1),
+            (Some(_), false) => None,
+        };
+
+        self.history_index = index;
+        self.input = index.map(|i| self.history[i].clone()).unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod search_tests {
+    use super::*;
+    use crate::file;
+
+    fn entries(names: &[&str]) -> Vec<FileData> {
+        names
+            .iter()
+            .map(|name| file::file_data_for(name, name, false))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_match() {
+        let paths = entries(&["main.rs", "lib.rs", "Cargo.toml", "build.rs"]);
+        let pattern = Pattern::new("rs");
+        assert_eq!(find_match(&paths, &pattern, 0, true), Some(1));
+        assert_eq!(find_match(&paths, &pattern, 1, true), Some(3));
+        assert_eq!(find_match(&paths, &pattern, 3, true), Some(0));
+        assert_eq!(find_match(&paths, &pattern, 0, false), Some(3));
+        assert_eq!(find_match(&paths, &Pattern::new("toml"), 2, true), Some(2));
+        assert_eq!(find_match(&paths, &Pattern::new("zig"), 0, true), None);
+        assert_eq!(find_match(&[], &pattern, 0, true), None);
+    }
+
+    #[test]
+    fn test_find_same_extension() {
+        let paths = entries(&["main.rs", "Cargo.toml", "lib.rs", "README"]);
+        assert_eq!(find_same_extension(&paths, 0, true), Some(2));
+        assert_eq!(find_same_extension(&paths, 2, true), Some(0));
+        assert_eq!(find_same_extension(&paths, 1, true), Some(1));
+        assert_eq!(find_same_extension(&paths, 3, true), None);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut search = Search::default();
+        for term in ["one", "two", "one"] {
+            search.start(true, 0);
+            search.input = term.to_owned();
+            search.confirm();
+        }
+
+        search.start(true, 0);
+        search.browse_history(true);
+        assert_eq!(search.input, "one");
+        search.browse_history(true);
+        assert_eq!(search.input, "two");
+        search.browse_history(true);
+        assert_eq!(search.input, "two");
+        search.browse_history(false);
+        assert_eq!(search.input, "one");
+        search.browse_history(false);
+        assert_eq!(search.input, "");
+    }
+}
@@ -0,0 +1,151 @@
+use crate::file::FileData;
+use crate::grep::Pattern;
+use std::collections::BTreeSet;
+
+/// Entries picked for multi-file operations, keyed by absolute path so that the selection
+/// survives filtering and moving between directories.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selection {
+    paths: BTreeSet<String>,
+}
+
+/// Matches `name` against a glob where `*` stands for any run of characters and `?` for a
+/// single one.
+pub fn glob_match(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut g, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                backtrack = Some((g, n));
+                g += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    g = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|c| *c == '*')
+}
+
+/// Globs match the whole name, anything else matches like `/`.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    if pattern.contains(['*', '?']) {
+        glob_match(pattern, name)
+    } else {
+        !Pattern::new(pattern).find_all(name).is_empty()
+    }
+}
+
+impl Selection {
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.contains(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        self.paths.iter().cloned().collect()
+    }
+
+    pub fn toggle(&mut self, path: &str) {
+        if !self.paths.remove(path) {
+            self.paths.insert(path.to_owned());
+        }
+    }
+
+    pub fn insert(&mut self, path: &str) {
+        self.paths.insert(path.to_owned());
+    }
+
+    pub fn remove(&mut self, path: &str) {
+        self.paths.remove(path);
+    }
+
+    pub fn clear(&mut self) {
+        self.paths.clear();
+    }
+
+    pub fn select_all(&mut self, entries: &[FileData]) {
+        for fd in entries {
+            self.insert(&fd.absolute);
+        }
+    }
+
+    /// Flips the selection state of every entry in `entries`, leaving other paths alone.
+    pub fn invert(&mut self, entries: &[FileData]) {
+        for fd in entries {
+            self.toggle(&fd.absolute);
+        }
+    }
+
+    /// Selects the entries whose name matches `pattern`, returning how many matched.
+    pub fn select_matching(&mut self, entries: &[FileData], pattern: &str) -> usize {
+        let matching: Vec<&FileData> = entries
+            .iter()
+            .filter(|fd| matches_pattern(pattern, &fd.shortname))
+            .collect();
+        for fd in &matching {
+            self.insert(&fd.absolute);
+        }
+        matching.len()
+    }
+}
+
+#[cfg(test)]
+mod selection_tests {
+    use super::*;
+    use crate::file;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(!glob_match("*.rs", "main.rsx"));
+        assert!(glob_match("ma?n.*", "main.rs"));
+        assert!(glob_match("*a*b*", "xxaxxbxx"));
+        assert!(!glob_match("*a*b", "xxbxxa"));
+        assert!(glob_match("*", ""));
+    }
+
+    #[test]
+    fn test_selection() {
+        let entries: Vec<FileData> = ["/a/main.rs", "/a/lib.rs", "/a/Cargo.toml"]
+            .iter()
+            .map(|path| file::file_data_for(path, &path[3..], false))
+            .collect();
+        let mut selection = Selection::default();
+        selection.insert("/elsewhere/kept");
+
+        assert_eq!(selection.select_matching(&entries, "*.rs"), 2);
+        assert_eq!(selection.len(), 3);
+
+        selection.invert(&entries);
+        assert_eq!(selection.paths(), vec!["/a/Cargo.toml", "/elsewhere/kept"]);
+
+        selection.toggle("/a/Cargo.toml");
+        selection.select_all(&entries);
+        assert_eq!(selection.len(), 4);
+
+        selection.clear();
+        assert!(selection.is_empty());
+        assert_eq!(selection.select_matching(&entries, "cargo"), 1);
+    }
+}
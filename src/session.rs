@@ -0,0 +1,164 @@
+use crate::config;
+use crate::state_handler::ViewMode;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+pub const DEFAULT_NAME: &str = "default";
+
+/// Everything needed to pick up where a previous run left off. Stored as `key = value` lines in
+/// `$XDG_DATA_HOME/slingshot/sessions/<name>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Session {
+    pub dir: String,
+    pub selected: Option<String>,
+    pub filter: String,
+    pub view_mode: ViewMode,
+    pub tree: bool,
+    pub expanded: Vec<String>,
+    pub hex: bool,
+    pub marks: Vec<(char, String)>,
+    pub selection: Vec<String>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            dir: String::new(),
+            selected: None,
+            filter: String::new(),
+            view_mode: ViewMode::List,
+            tree: false,
+            expanded: Vec::new(),
+            hex: false,
+            marks: Vec::new(),
+            selection: Vec::new(),
+        }
+    }
+}
+
+/// Session names become file names, so they may not contain path separators.
+pub fn session_path(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    if name.is_empty() || name.contains('/') || name.starts_with('.') {
+        return Err(format!("Invalid session name: {}", name).into());
+    }
+    let dir = config::data_dir().ok_or("Could not find the data directory")?;
+    Ok(dir.join("sessions").join(name))
+}
+
+fn view_mode_name(view_mode: &ViewMode) -> &'static str {
+    match view_mode {
+        ViewMode::List => "list",
+        ViewMode::Split => "split",
+        ViewMode::Miller => "miller",
+    }
+}
+
+impl Session {
+    pub fn parse(contents: &str) -> Session {
+        let mut session = Session::default();
+        for line in contents.lines() {
+            let (key, value) = match line.split_once(" = ") {
+                Some((key, value)) => (key.trim(), value),
+                None => continue,
+            };
+
+            match key {
+                "dir" => session.dir = value.to_owned(),
+                "selected" => session.selected = Some(value.to_owned()),
+                "filter" => session.filter = value.to_owned(),
+                "view" => {
+                    session.view_mode = match value {
+                        "split" => ViewMode::Split,
+                        "miller" => ViewMode::Miller,
+                        _ => ViewMode::List,
+                    }
+                }
+                "tree" => session.tree = value == "true",
+                "expanded" => session.expanded.push(value.to_owned()),
+                "hex" => session.hex = value == "true",
+                "mark" => {
+                    let mut chars = value.chars();
+                    if let (Some(name), Some(' ')) = (chars.next(), chars.next()) {
+                        session.marks.push((name, chars.as_str().to_owned()));
+                    }
+                }
+                "selection" => session.selection.push(value.to_owned()),
+                _ => {}
+            }
+        }
+        session
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut output = format!("dir = {}\n", self.dir);
+        if let Some(selected) = &self.selected {
+            output.push_str(&format!("selected = {}\n", selected));
+        }
+        output.push_str(&format!("filter = {}\n", self.filter));
+        output.push_str(&format!("view = {}\n", view_mode_name(&self.view_mode)));
+        output.push_str(&format!("tree = {}\n", self.tree));
+        for expanded in &self.expanded {
+            output.push_str(&format!("expanded = {}\n", expanded));
+        }
+        output.push_str(&format!("hex = {}\n", self.hex));
+        for (name, target) in &self.marks {
+            output.push_str(&format!("mark = {} {}\n", name, target));
+        }
+        for path in &self.selection {
+            output.push_str(&format!("selection = {}\n", path));
+        }
+        output
+    }
+
+    pub fn load(name: &str) -> Result<Session, Box<dyn Error>> {
+        let path = session_path(name)?;
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read session {}: {}", name, e))?;
+        Ok(Session::parse(&contents))
+    }
+
+    pub fn save(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        let path = session_path(name)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::File::create(&tmp)?.write_all(self.serialize().as_bytes())?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod session_tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let session = Session {
+            dir: "/home/user/code".to_owned(),
+            selected: Some("/home/user/code/a b.rs".to_owned()),
+            filter: " b".to_owned(),
+            view_mode: ViewMode::Miller,
+            tree: true,
+            expanded: vec!["/home/user/code/src".to_owned()],
+            hex: true,
+            marks: vec![('a', "/home/user/code/src".to_owned())],
+            selection: vec!["/home/user/code/a b.rs".to_owned()],
+        };
+        assert_eq!(Session::parse(&session.serialize()), session);
+        assert_eq!(
+            Session::parse("garbage\nview = unknown"),
+            Session::default()
+        );
+    }
+
+    #[test]
+    fn test_session_path() {
+        assert!(session_path("../escape").is_err());
+        assert!(session_path("").is_err());
+        assert!(session_path(".hidden").is_err());
+    }
+}
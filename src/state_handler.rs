@@ -1,5 +1,24 @@
+use crate::config;
+use crate::deletion;
+use crate::dirstate;
 use crate::file;
+use crate::finder;
+use crate::frecency;
+use crate::grep;
+use crate::index;
+use crate::journal;
+use crate::jumplist;
+use crate::layout;
+use crate::marks;
+use crate::preview;
+use crate::register;
+use crate::rename;
+use crate::search;
+use crate::selection;
+use crate::session;
 use crate::styles;
+use crate::trash;
+use crate::tree;
 use crossterm::event::KeyCode;
 use crossterm::style::{Attribute, ResetColor, SetAttribute, SetForegroundColor};
 use crossterm::terminal;
@@ -10,126 +29,1604 @@ use std::env;
 use std::error::Error;
 use std::fs::{self, metadata};
 use std::io::{stdout, Write};
+use std::path::Path;
 use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum KeybindMode {
     Normal,
     Insert,
-    //Visual,
-    //Deletion,
+    Visual,
+    /// A `ConfirmDialog` covers the screen.
+    Dialog,
+    Rename,
+    /// A paste waits for what to do with a name that is taken.
+    Conflict,
+}
+
+/// What a confirmed `ConfirmDialog` does.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum DialogAction {
+    #[default]
+    Delete,
+    PurgeTrashed,
+    EmptyTrash,
+    BulkRename,
+    /// Only shows what went wrong; confirming or cancelling just closes it.
+    Report,
+}
+
+/// A modal asking to confirm an action. Listed lines can be scrolled; with `typed` set the
+/// action is only confirmed once that text has been typed out.
+#[derive(Clone, Debug, Default)]
+pub struct ConfirmDialog {
+    pub title: String,
+    pub lines: Vec<String>,
+    pub typed: Option<String>,
+    pub input: String,
+    pub scroll: usize,
+    pub action: DialogAction,
+}
+
+impl ConfirmDialog {
+    pub fn new(
+        title: &str,
+        lines: Vec<String>,
+        typed: Option<&str>,
+        action: DialogAction,
+    ) -> ConfirmDialog {
+        ConfirmDialog {
+            title: title.to_owned(),
+            lines,
+            typed: typed.map(|text| text.to_owned()),
+            input: String::new(),
+            scroll: 0,
+            action,
+        }
+    }
+
+    /// Whether Enter (or `y` when nothing has to be typed) confirms the action.
+    pub fn accepts(&self) -> bool {
+        match &self.typed {
+            Some(text) => &self.input == text,
+            None => true,
+        }
+    }
+
+    pub fn scroll(&mut self, down: bool) {
+        if down {
+            self.scroll = cmp::min(self.scroll + 1, self.lines.len().saturating_sub(1));
+        } else {
+            self.scroll = self.scroll.saturating_sub(1);
+        }
+    }
+
+    fn prompt(&self) -> String {
+        if self.action == DialogAction::Report {
+            return "[Enter, Esc] close, [j, k] scroll".to_owned();
+        }
+        match &self.typed {
+            Some(text) => format!(
+                "Type \"{}\" and press Enter to confirm, Esc to cancel: {}",
+                text, self.input
+            ),
+            None => "[y] confirm, [n] cancel, [j, k] scroll".to_owned(),
+        }
+    }
+
+    fn display(&self) -> Result<(), Box<dyn Error>> {
+        print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+        let mut stdout = stdout();
+        let (_, height) = terminal::size()?;
+        let t_height = cmp::max(height, 1) - 1;
+
+        stdout.queue(cursor::MoveTo(0, 0))?;
+        print!(
+            "{}{}{}{}",
+            SetAttribute(Attribute::Bold),
+            SetForegroundColor(styles::ERR),
+            self.title,
+            ResetColor
+        );
+
+        let rows = t_height.saturating_sub(3) as usize;
+        for (row, line) in self.lines.iter().skip(self.scroll).take(rows).enumerate() {
+            stdout.queue(cursor::MoveTo(0, 2 + row as u16))?;
+            print!("{}", line);
+        }
+
+        stdout.queue(cursor::MoveTo(0, t_height))?;
+        print!("{}", self.prompt());
+        stdout.flush()?;
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum AppMode {
     FileExplorer,
     Command,
+    Find,
+    Grep,
+    IndexSearch,
+    Jump,
+    Bookmarks,
+    Search,
+    Trash,
+    History,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ViewMode {
+    List,
+    Split,
+    Miller,
 }
 
 #[derive(Clone)]
 pub struct AppState {
     pub app_mode: AppMode,
     pub keybind_mode: KeybindMode,
+    pub view_mode: ViewMode,
     pub curr_absolute_path: String,
     pub inner_paths: Vec<file::FileData>,
+    pub parent_paths: Vec<file::FileData>,
     pub displayed_paths: Vec<file::FileData>,
     pub selected_index: usize,
+    pub scroll_offset: usize,
     pub user_input: String,
     pub message: String,
     pub command_mode: bool,
+    pub preview: preview::Previewer,
+    pub tree: tree::Tree,
+    pub finder: finder::Finder,
+    pub grep: grep::Grep,
+    pub index: index::IndexSearch,
+    pub frecency: frecency::Frecency,
+    pub marks: marks::Marks,
+    pub pending_key: Option<char>,
+    pub count: Option<usize>,
+    pub list_rows: usize,
+    pub search: search::Search,
+    pub visual_anchor: usize,
+    pub register: register::Register,
+    pub paste: register::Paste,
+    pub selection: selection::Selection,
+    /// Entries marked for deletion with `Delete`, kept by path like the selection.
+    pub marked: selection::Selection,
+    pub deletion: deletion::Review,
+    pub dialog: ConfirmDialog,
+    pub trash_items: Vec<trash::TrashedItem>,
+    pub journal: journal::Journal,
+    pub rename: rename::InlineRename,
+    pub bulk_rename: rename::BulkRename,
+    pub command_targets: Vec<String>,
+    pub jump_list: jumplist::JumpList,
+    pub dir_states: dirstate::DirStates,
+    pub session_name: String,
+    pub config: config::Config,
 }
 
 impl AppState {
-    pub fn display(&self) -> Result<(), Box<dyn Error>> {
+    pub fn display(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.keybind_mode == KeybindMode::Dialog {
+            return self.dialog.display();
+        }
+
         match self.app_mode {
-            AppMode::FileExplorer => {
+            AppMode::FileExplorer
+            | AppMode::Find
+            | AppMode::IndexSearch
+            | AppMode::Jump
+            | AppMode::Bookmarks
+            | AppMode::Search
+            | AppMode::Trash
+            | AppMode::History => {
                 print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
                 let mut stdout = stdout();
                 stdout.queue(cursor::MoveTo(0, 1))?;
 
+                let area = match self.view_mode {
+                    ViewMode::List => layout::list_area(),
+                    ViewMode::Split => {
+                        let (list_area, preview_area) =
+                            layout::split_columns(layout::list_area(), 40);
+                        self.display_preview(preview_area, &mut stdout)?;
+                        list_area
+                    }
+                    ViewMode::Miller => {
+                        let (parent_area, rest) = layout::split_columns(layout::list_area(), 20);
+                        let (list_area, preview_area) = layout::split_columns(rest, 45);
+                        self.display_parent(parent_area, &mut stdout);
+                        self.display_separator(list_area, &mut stdout)?;
+                        self.display_preview(preview_area, &mut stdout)?;
+                        list_area
+                    }
+                };
+                self.list_rows = area.height as usize;
+                self.scroll_offset = layout::scroll_offset(
+                    self.selected_index,
+                    self.scroll_offset,
+                    area.height as usize,
+                );
+                let highlight = self.search.highlight(self.app_mode == AppMode::Search);
+                let decorations = file::Decorations {
+                    highlight: highlight.as_ref(),
+                    visual: self.visual_range(),
+                    selected: Some(&self.selection),
+                    marked: Some(&self.marked),
+                };
                 file::print_file_data(
                     Cow::Borrowed(&self.displayed_paths),
                     self.selected_index,
+                    self.scroll_offset,
+                    decorations,
+                    area,
                     &mut stdout,
                 );
+                if self.keybind_mode == KeybindMode::Rename {
+                    self.display_rename(area, &mut stdout)?;
+                }
 
                 let (_, height) = terminal::size()?;
                 let t_height = cmp::max(height, 1) - 1;
                 stdout.queue(cursor::MoveTo(0, t_height))?;
-                print!("{}{}", self.message, ResetColor);
+                if self.app_mode == AppMode::Search {
+                    print!("{}", self.search_prompt());
+                } else if self.shows_status() && self.message.is_empty() {
+                    print!(
+                        "{}{}{}",
+                        SetForegroundColor(styles::LIGHT_CONTRAST),
+                        self.status_line(),
+                        ResetColor
+                    );
+                } else {
+                    print!("{}{}", self.message, ResetColor);
+                }
+
+                stdout.queue(cursor::MoveTo(0, 0))?;
+                if self.app_mode == AppMode::Find {
+                    print!("{}find>{} ", SetForegroundColor(styles::ERR), ResetColor);
+                } else if self.app_mode == AppMode::IndexSearch {
+                    print!("{}index>{} ", SetForegroundColor(styles::ERR), ResetColor);
+                } else if self.app_mode == AppMode::Jump {
+                    print!("{}jump>{} ", SetForegroundColor(styles::ERR), ResetColor);
+                } else if self.app_mode == AppMode::Bookmarks {
+                    print!("{}marks{}", SetForegroundColor(styles::ERR), ResetColor);
+                } else if self.app_mode == AppMode::Trash {
+                    print!("{}trash{}", SetForegroundColor(styles::ERR), ResetColor);
+                } else if self.app_mode == AppMode::History {
+                    print!("{}history{}", SetForegroundColor(styles::ERR), ResetColor);
+                } else {
+                    print!(
+                        ".{}{}/",
+                        SetAttribute(Attribute::Bold),
+                        self.curr_absolute_path
+                    );
+                }
+
+                print!("{}", self.user_input);
+                if self.app_mode == AppMode::Search {
+                    let column = layout::str_width(&self.search_prompt());
+                    stdout.queue(cursor::MoveTo(column as u16, t_height))?;
+                } else if self.keybind_mode == KeybindMode::Rename {
+                    let row = self.selected_index.saturating_sub(self.scroll_offset);
+                    let column = self.rename_cursor_column(area);
+                    stdout.queue(cursor::MoveTo(column, area.y + row as u16))?;
+                } else if self.keybind_mode != KeybindMode::Insert {
+                    let row = self.selected_index.saturating_sub(self.scroll_offset);
+                    stdout.queue(cursor::MoveTo(0, area.y + row as u16))?;
+                }
+                stdout.flush()?;
+            }
+            AppMode::Command => {
+                print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+                let mut stdout = stdout();
+                stdout.queue(cursor::MoveTo(0, 0))?;
+                print!(
+                    "{}{}",
+                    SetAttribute(Attribute::Bold),
+                    self.curr_absolute_path
+                );
+                stdout.queue(cursor::MoveTo(0, 2))?;
+                let msg_split: Vec<&str> = self.message.split("\n").collect();
+                if msg_split.len() > 1 {
+                    for line in msg_split {
+                        println!("{}", line);
+                        stdout.queue(cursor::MoveToColumn(0))?;
+                    }
+                } else {
+                    print!("{}", self.message);
+                }
+                stdout.queue(cursor::MoveTo(0, 1))?;
+                print!("{}{}{} ", SetForegroundColor(styles::ERR), ">", ResetColor);
+                print!("{}", self.user_input);
+                if !self.command_targets.is_empty() {
+                    let (_, height) = terminal::size()?;
+                    stdout.queue(cursor::SavePosition)?;
+                    stdout.queue(cursor::MoveTo(0, cmp::max(height, 1) - 1))?;
+                    print!(
+                        "{}Runs with {} selected entries appended{}",
+                        SetForegroundColor(styles::LIGHT_CONTRAST),
+                        self.command_targets.len(),
+                        ResetColor
+                    );
+                    stdout.queue(cursor::RestorePosition)?;
+                }
+                stdout.flush()?;
+            }
+            AppMode::Grep => {
+                print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+                let mut stdout = stdout();
+
+                let area = layout::list_area();
+                self.scroll_offset = layout::scroll_offset(
+                    self.selected_index,
+                    self.scroll_offset,
+                    area.height as usize,
+                );
+                grep::print_matches(
+                    &self.grep.snapshot(),
+                    &self.grep.pattern,
+                    self.selected_index,
+                    self.scroll_offset,
+                    area,
+                    &mut stdout,
+                );
+
+                let (_, height) = terminal::size()?;
+                let t_height = cmp::max(height, 1) - 1;
+                stdout.queue(cursor::MoveTo(0, t_height))?;
+                if self.message.is_empty() {
+                    print!(
+                        "{}{}{}",
+                        SetForegroundColor(styles::LIGHT_CONTRAST),
+                        self.grep_status(),
+                        ResetColor
+                    );
+                } else {
+                    print!("{}{}", self.message, ResetColor);
+                }
+
+                stdout.queue(cursor::MoveTo(0, 0))?;
+                print!("{}grep>{} ", SetForegroundColor(styles::ERR), ResetColor);
+                print!("{}", self.user_input);
+                stdout.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn display_separator(
+        &self,
+        area: layout::Rect,
+        stdout: &mut std::io::Stdout,
+    ) -> Result<(), Box<dyn Error>> {
+        for row in 0..area.height {
+            stdout.queue(cursor::MoveTo(area.x - 1, area.y + row))?;
+            print!(
+                "{}│{}",
+                SetForegroundColor(styles::LIGHT_CONTRAST),
+                ResetColor
+            );
+        }
+        Ok(())
+    }
+
+    /// Draws the preview of the selected entry into `area`.
+    fn display_preview(
+        &mut self,
+        area: layout::Rect,
+        stdout: &mut std::io::Stdout,
+    ) -> Result<(), Box<dyn Error>> {
+        match self.displayed_paths.get(self.selected_index) {
+            Some(selected) => self.preview.request(&selected.absolute),
+            None => self.preview.clear(),
+        }
+
+        self.display_separator(area, stdout)?;
+        if !self.displayed_paths.is_empty() {
+            preview::print_preview(self.preview.current.as_ref(), area, stdout);
+        }
+        Ok(())
+    }
+
+    /// Row of the current directory in the parent listing and the scroll offset that keeps it
+    /// in view. At `/` there is no parent to find it in, so nothing is highlighted.
+    pub fn parent_position(&self, rows: usize) -> (Option<usize>, usize) {
+        let index = self
+            .parent_paths
+            .iter()
+            .position(|fd| fd.absolute == self.curr_absolute_path);
+        let offset = index.map_or(0, |index| layout::scroll_offset(index, 0, rows));
+        (index, offset)
+    }
+
+    /// Draws the parent directory into `area` with the current directory highlighted.
+    fn display_parent(&self, area: layout::Rect, stdout: &mut std::io::Stdout) {
+        let (index, offset) = self.parent_position(area.height as usize);
+
+        // An index past the end highlights no row.
+        file::print_file_data(
+            Cow::Borrowed(&self.parent_paths),
+            index.unwrap_or(self.parent_paths.len()),
+            offset,
+            file::Decorations::default(),
+            area,
+            stdout,
+        );
+    }
+
+    pub fn toggle_view_mode(&mut self) {
+        self.view_mode = match self.view_mode {
+            ViewMode::List => ViewMode::Split,
+            ViewMode::Split => ViewMode::Miller,
+            ViewMode::Miller => ViewMode::List,
+        };
+    }
+
+    pub fn toggle_hex_preview(&mut self) {
+        self.preview.toggle_hex();
+    }
+
+    /// Collects results from background work. Returns true when the screen should be redrawn.
+    pub fn poll_background(&mut self) -> bool {
+        let mut changed = self.preview.poll();
+        if self.app_mode == AppMode::Find && self.finder.poll() {
+            self.refresh_displayed_paths();
+            changed = true;
+        }
+        if self.app_mode == AppMode::Grep && self.grep.poll() {
+            changed = true;
+        }
+        changed
+    }
+
+    /// Number of rows in the list the selection moves through.
+    fn list_len(&self) -> usize {
+        match self.app_mode {
+            AppMode::Grep => self.grep.len(),
+            _ => self.displayed_paths.len(),
+        }
+    }
+
+    fn grep_status(&self) -> String {
+        if self.grep.root.is_empty() {
+            return "Type a pattern and press Enter to search file contents".to_owned();
+        }
+
+        if self.grep.is_truncated() {
+            return format!(
+                "first {} matches under {}, refine the pattern to see the rest",
+                self.grep.len(),
+                self.grep.root
+            );
+        }
+        let state = if self.grep.is_finished() {
+            ""
+        } else {
+            " (searching…)"
+        };
+        format!(
+            "{} matching lines under {}{}",
+            self.grep.len(),
+            self.grep.root,
+            state
+        )
+    }
+
+    pub fn start_grep_mode(&mut self) {
+        self.remember_dir_state();
+        self.finder.cancel();
+        self.app_mode = AppMode::Grep;
+        self.keybind_mode = KeybindMode::Insert;
+        self.grep = grep::Grep::default();
+        self.user_input = "".to_owned();
+        self.message = "".to_owned();
+        self.selected_index = 0;
+    }
+
+    pub fn exit_grep_mode(&mut self) {
+        self.grep.cancel();
+        self.app_mode = AppMode::FileExplorer;
+        self.keybind_mode = KeybindMode::Normal;
+        self.user_input = "".to_owned();
+        self.selected_index = 0;
+        self.restore_dir_state();
+    }
+
+    /// Runs a new search when the pattern changed, otherwise opens the selected match in the
+    /// editor at its line.
+    fn handle_enter_grep(&mut self) {
+        if self.grep.root.is_empty() || self.grep.pattern != grep::Pattern::new(&self.user_input) {
+            if self.user_input.is_empty() {
+                return;
+            }
+            self.grep.cancel();
+            self.grep = grep::Grep::start(&self.curr_absolute_path, &self.user_input, &self.config);
+            self.selected_index = 0;
+            self.scroll_offset = 0;
+            return;
+        }
+
+        if let Some(found) = self.grep.get(self.selected_index) {
+            self.open_in_editor(&found.absolute, Some(found.line));
+        }
+    }
+
+    /// Draws the name being edited over the selected row, with the kept extension dimmed.
+    fn display_rename(
+        &self,
+        area: layout::Rect,
+        stdout: &mut std::io::Stdout,
+    ) -> Result<(), Box<dyn Error>> {
+        let icon = match self.displayed_paths.get(self.selected_index) {
+            Some(fd) => &fd.icon,
+            None => return Ok(()),
+        };
+        let row = self.selected_index.saturating_sub(self.scroll_offset) as u16;
+        stdout.queue(cursor::MoveTo(area.x, area.y + row))?;
+        print!("{:width$}", "", width = area.width as usize);
+        stdout.queue(cursor::MoveTo(area.x, area.y + row))?;
+        print!(
+            "{}{}{} {}{}{}{}{}",
+            SetAttribute(Attribute::Bold),
+            self.selected_index,
+            ResetColor,
+            icon,
+            ResetColor,
+            self.rename.editor.text(),
+            SetForegroundColor(styles::LIGHT_CONTRAST),
+            self.rename.suffix
+        );
+        print!("{}", ResetColor);
+        Ok(())
+    }
+
+    fn rename_cursor_column(&self, area: layout::Rect) -> u16 {
+        let icon = self
+            .displayed_paths
+            .get(self.selected_index)
+            .map_or("", |fd| fd.icon.as_str());
+        let prefix = format!("{} {}", self.selected_index, icon);
+        let column =
+            layout::str_width(&prefix) + layout::str_width(&self.rename.editor.before_cursor());
+        area.x + column as u16
+    }
+
+    fn shows_status(&self) -> bool {
+        match self.app_mode {
+            AppMode::Find
+            | AppMode::IndexSearch
+            | AppMode::Jump
+            | AppMode::Bookmarks
+            | AppMode::Trash
+            | AppMode::History => true,
+            AppMode::FileExplorer => !self.selection.is_empty() || !self.marked.is_empty(),
+            _ => false,
+        }
+    }
+
+    fn status_line(&self) -> String {
+        if self.app_mode == AppMode::FileExplorer {
+            let mut counts = Vec::new();
+            if !self.selection.is_empty() {
+                counts.push(format!("{} selected", self.selection.len()));
+            }
+            if !self.marked.is_empty() {
+                counts.push(format!("{} marked for deletion", self.marked.len()));
+            }
+            return counts.join(", ");
+        }
+        if self.app_mode == AppMode::Bookmarks {
+            return "[Enter] jump, [d] delete, [c] remove missing, [Esc] close".to_owned();
+        }
+        if self.app_mode == AppMode::History {
+            return "[u] undo, [Ctrl+R] redo, [Esc] close".to_owned();
+        }
+        if self.app_mode == AppMode::Trash {
+            return "[Enter] restore, [d] delete permanently, [E] empty the trash, [Esc] close"
+                .to_owned();
+        }
+        if self.app_mode == AppMode::IndexSearch {
+            return format!(
+                "{} of {} indexed entries",
+                self.displayed_paths.len(),
+                self.index.len()
+            );
+        }
+        if self.app_mode == AppMode::Jump {
+            return format!(
+                "{} of {} visited directories",
+                self.displayed_paths.len(),
+                self.frecency.entries.len()
+            );
+        }
+
+        let state = if self.finder.is_finished() {
+            ""
+        } else {
+            " (searching…)"
+        };
+        format!(
+            "{} of {} entries under {}{}",
+            self.displayed_paths.len(),
+            self.finder.len(),
+            self.finder.root,
+            state
+        )
+    }
+
+    /// Starts a recursive search of the current directory in a background thread.
+    pub fn start_find_mode(&mut self) {
+        self.remember_dir_state();
+        self.finder.cancel();
+        self.finder = finder::Finder::start(&self.curr_absolute_path, &self.config);
+        self.app_mode = AppMode::Find;
+        self.keybind_mode = KeybindMode::Insert;
+        self.user_input = "".to_owned();
+        self.message = "".to_owned();
+        self.selected_index = 0;
+        self.refresh_displayed_paths();
+    }
+
+    /// Searches the on-disk index written by `slingshot index`, loading it on first use.
+    pub fn start_index_mode(&mut self) {
+        if self.index.is_empty() {
+            if let Some(path) = index::index_path() {
+                self.index = index::IndexSearch::load(&path);
+            }
+        }
+        if self.index.is_empty() {
+            self.message = "No index found, run `slingshot index` first".to_owned();
+            return;
+        }
+
+        self.remember_dir_state();
+        self.finder.cancel();
+        self.app_mode = AppMode::IndexSearch;
+        self.keybind_mode = KeybindMode::Insert;
+        self.user_input = "".to_owned();
+        self.message = "".to_owned();
+        self.selected_index = 0;
+        self.refresh_displayed_paths();
+    }
+
+    /// Lists visited directories ranked by how often and how recently they were visited.
+    pub fn start_jump_mode(&mut self) {
+        if self.frecency.entries.is_empty() {
+            self.message = "No visited directories yet".to_owned();
+            return;
+        }
+
+        self.remember_dir_state();
+        self.finder.cancel();
+        self.app_mode = AppMode::Jump;
+        self.keybind_mode = KeybindMode::Insert;
+        self.user_input = "".to_owned();
+        self.message = "".to_owned();
+        self.selected_index = 0;
+        self.refresh_displayed_paths();
+    }
+
+    pub fn exit_find_mode(&mut self) {
+        self.finder.cancel();
+        self.app_mode = AppMode::FileExplorer;
+        self.keybind_mode = KeybindMode::Normal;
+        self.user_input = "".to_owned();
+        self.selected_index = 0;
+        self.restore_dir_state();
+    }
+
+    fn handle_enter_find(&mut self) {
+        let selected = match self.displayed_paths.get(self.selected_index) {
+            Some(selected) => selected.clone(),
+            None => return,
+        };
+
+        if !selected.is_dir {
+            self.open_in_editor(&selected.absolute, None);
+            return;
+        }
+
+        if let Err(e) = std::env::set_current_dir(&selected.absolute) {
+            self.message = e.to_string();
+            return;
+        }
+
+        self.exit_find_mode();
+        self.update_post_move(&selected.absolute);
+    }
+
+    /// Leaves find mode and moves to the directory containing the selected result, with the
+    /// result selected.
+    pub fn handle_reveal(&mut self) {
+        if let Some(selected) = self.displayed_paths.get(self.selected_index) {
+            let target = selected.absolute.clone();
+            self.reveal_path(&target);
+        }
+    }
+
+    /// Moves to the directory containing `target` and selects it.
+    fn reveal_path(&mut self, target: &str) {
+        let parent = match Path::new(target).parent() {
+            Some(parent) => parent.to_string_lossy().into_owned(),
+            None => return,
+        };
+
+        if let Err(e) = std::env::set_current_dir(&parent) {
+            self.message = e.to_string();
+            return;
+        }
+
+        self.exit_find_mode();
+        self.update_post_move(&parent);
+        if let Some(index) = self
+            .displayed_paths
+            .iter()
+            .position(|fd| fd.absolute == target)
+        {
+            self.selected_index = index;
+        }
+    }
+
+    /// Marks the selected file, or the current directory when a directory is selected.
+    pub fn handle_set_mark(&mut self, name: char) {
+        let target = match self.displayed_paths.get(self.selected_index) {
+            Some(selected) if !selected.is_dir => selected.absolute.clone(),
+            _ => self.curr_absolute_path.clone(),
+        };
+
+        self.message = match self.marks.set(name, &target) {
+            Ok(()) => format!("Mark '{}' set to {}", name, target),
+            Err(e) => e.to_string(),
+        };
+    }
+
+    pub fn handle_jump_to_mark(&mut self, name: char) {
+        let target = match self.marks.get(name) {
+            Some(target) => target.clone(),
+            None => {
+                self.message = format!("Mark '{}' is not set", name);
+                return;
+            }
+        };
+
+        if Path::new(&target).is_dir() {
+            if let Err(e) = std::env::set_current_dir(&target) {
+                self.message = e.to_string();
+                return;
+            }
+            self.exit_find_mode();
+            self.update_post_move(&target);
+        } else if Path::new(&target).exists() {
+            self.reveal_path(&target);
+        } else {
+            self.message = format!("Mark '{}' points to a missing path: {}", name, target);
+        }
+    }
+
+    pub fn start_bookmarks_mode(&mut self) {
+        if self.marks.is_empty() {
+            self.message = "No marks set, use m{a-z} or m{A-Z}".to_owned();
+            return;
+        }
+
+        self.remember_dir_state();
+        self.app_mode = AppMode::Bookmarks;
+        self.keybind_mode = KeybindMode::Normal;
+        self.user_input = "".to_owned();
+        self.message = "".to_owned();
+        self.selected_index = 0;
+        self.refresh_displayed_paths();
+    }
+
+    fn selected_bookmark(&self) -> Option<char> {
+        self.marks
+            .list()
+            .get(self.selected_index)
+            .map(|(name, _)| *name)
+    }
+
+    fn handle_enter_bookmark(&mut self) {
+        if let Some(name) = self.selected_bookmark() {
+            self.handle_jump_to_mark(name);
+        }
+    }
+
+    pub fn handle_delete_bookmark(&mut self) {
+        if let Some(name) = self.selected_bookmark() {
+            if let Err(e) = self.marks.remove(name) {
+                self.message = e.to_string();
+            }
+            self.refresh_displayed_paths();
+        }
+    }
+
+    pub fn handle_remove_missing_bookmarks(&mut self) {
+        self.message = match self.marks.remove_missing() {
+            Ok(count) => format!("Removed {} missing marks", count),
+            Err(e) => e.to_string(),
+        };
+        self.refresh_displayed_paths();
+    }
+
+    fn bookmark_entries(&self) -> Vec<file::FileData> {
+        self.marks
+            .list()
+            .into_iter()
+            .map(|(name, target)| {
+                let path = Path::new(&target);
+                let mut shortname = format!("{}  {}", name, config::abbreviate_home(&target));
+                if !path.exists() {
+                    shortname.push_str("  [missing]");
+                }
+                file::file_data_for(&target, &shortname, path.is_dir())
+            })
+            .collect()
+    }
+
+    pub fn start_trash_mode(&mut self) {
+        self.load_trash_items();
+        if self.trash_items.is_empty() {
+            self.message = "The trash is empty".to_owned();
+            return;
+        }
+
+        self.remember_dir_state();
+        self.app_mode = AppMode::Trash;
+        self.keybind_mode = KeybindMode::Normal;
+        self.user_input = "".to_owned();
+        self.message = "".to_owned();
+        self.selected_index = 0;
+        self.refresh_displayed_paths();
+    }
+
+    fn load_trash_items(&mut self) {
+        self.trash_items = trash::trashes()
+            .iter()
+            .flat_map(|trash| trash.items())
+            .collect();
+        self.trash_items.sort_by(|a, b| b.deleted.cmp(&a.deleted));
+    }
+
+    /// Reloads the trash after a change, leaving the view once nothing is left in it.
+    fn refresh_trash(&mut self) {
+        self.load_trash_items();
+        if self.trash_items.is_empty() {
+            self.exit_trash_mode();
+        } else {
+            self.refresh_displayed_paths();
+        }
+    }
+
+    pub fn exit_trash_mode(&mut self) {
+        // Restored entries may have landed in the current directory.
+        let paths = file::get_paths(&self.curr_absolute_path);
+        if let Ok(paths) = file::generate_file_data(paths) {
+            self.inner_paths = paths;
+        }
+        self.exit_find_mode();
+    }
+
+    fn trash_entries(&self) -> Vec<file::FileData> {
+        self.trash_items
+            .iter()
+            .map(|item| {
+                let path = item.path();
+                let shortname = format!(
+                    "{}  {}",
+                    item.deleted.replace('T', " "),
+                    config::abbreviate_home(&item.original)
+                );
+                file::file_data_for(&path.to_string_lossy(), &shortname, path.is_dir())
+            })
+            .collect()
+    }
+
+    fn handle_restore_trashed(&mut self) {
+        let item = match self.trash_items.get(self.selected_index) {
+            Some(item) => item.clone(),
+            None => return,
+        };
+
+        self.message = match item.restore() {
+            Ok(target) => {
+                let path = target.to_string_lossy().into_owned();
+                let message = format!("Restored {}", config::abbreviate_home(&path));
+                let operation = journal::Operation::Create {
+                    path,
+                    trashed: None,
+                };
+                self.journal.record(&message, vec![operation]);
+                message
+            }
+            Err(e) => format!("Could not restore {}: {}", item.original, e),
+        };
+        self.refresh_trash();
+    }
+
+    /// Asks before deleting the selected trashed entry for good.
+    pub fn start_purge_trashed(&mut self) {
+        let item = match self.trash_items.get(self.selected_index) {
+            Some(item) => item.clone(),
+            None => return,
+        };
+
+        let review = deletion::Review::new(&[item.path().to_string_lossy().into_owned()], true);
+        let typed = Some(deletion::CONFIRMATION).filter(|_| review.needs_typed_confirmation());
+        self.dialog = ConfirmDialog::new(
+            &format!(
+                "Permanently delete {}?",
+                config::abbreviate_home(&item.original)
+            ),
+            review.lines(),
+            typed,
+            DialogAction::PurgeTrashed,
+        );
+        self.keybind_mode = KeybindMode::Dialog;
+    }
+
+    fn purge_trashed(&mut self) {
+        if let Some(item) = self.trash_items.get(self.selected_index).cloned() {
+            self.message = match item.purge() {
+                Ok(()) => {
+                    let message = format!("Deleted {}", config::abbreviate_home(&item.original));
+                    let operation = journal::Operation::Delete {
+                        path: item.original,
+                    };
+                    self.journal.record(&message, vec![operation]);
+                    message
+                }
+                Err(e) => format!("Could not delete {}: {}", item.original, e),
+            };
+        }
+        self.refresh_trash();
+    }
+
+    /// Asks before permanently deleting everything in the trash.
+    pub fn start_empty_trash(&mut self) {
+        let paths: Vec<String> = self
+            .trash_items
+            .iter()
+            .map(|item| item.path().to_string_lossy().into_owned())
+            .collect();
+        let review = deletion::Review::new(&paths, true);
+        let typed = Some(deletion::CONFIRMATION).filter(|_| review.needs_typed_confirmation());
+        self.dialog = ConfirmDialog::new(
+            &format!(
+                "Permanently delete all {} entries in the trash?",
+                paths.len()
+            ),
+            review.lines(),
+            typed,
+            DialogAction::EmptyTrash,
+        );
+        self.keybind_mode = KeybindMode::Dialog;
+    }
+
+    fn empty_trash(&mut self) {
+        let operations = self
+            .trash_items
+            .iter()
+            .map(|item| journal::Operation::Delete {
+                path: item.original.clone(),
+            })
+            .collect();
+        let mut removed = 0;
+        for trash in trash::trashes() {
+            match trash.empty() {
+                Ok(count) => removed += count,
+                Err(e) => {
+                    self.message = format!("Could not empty {}: {}", trash.dir.display(), e);
+                    self.refresh_trash();
+                    return;
+                }
+            }
+        }
+        self.message = format!("Deleted {} entries from the trash", removed);
+        self.journal.record(&self.message.clone(), operations);
+        self.refresh_trash();
+    }
+
+    fn open_in_editor(&mut self, path: &str, line: Option<usize>) {
+        let editor = config::editor();
+        let mut command = Command::new(&editor[0]);
+        command.args(&editor[1..]);
+        if let Some(line) = line {
+            command.arg(format!("+{}", line));
+        }
+
+        if let Err(e) = command.arg(path).status() {
+            self.message = e.to_string();
+        }
+    }
+
+    /// Rebuilds `displayed_paths` from `inner_paths`, the tree expansion state and the
+    /// current filter.
+    pub fn refresh_displayed_paths(&mut self) {
+        self.displayed_paths = if self.app_mode == AppMode::Find {
+            self.finder.ranked(&self.user_input)
+        } else if self.app_mode == AppMode::IndexSearch {
+            self.index.search(&self.user_input)
+        } else if self.app_mode == AppMode::Jump {
+            self.frecency.ranked(&self.user_input)
+        } else if self.app_mode == AppMode::Bookmarks {
+            self.bookmark_entries()
+        } else if self.app_mode == AppMode::Trash {
+            self.trash_entries()
+        } else if self.app_mode == AppMode::History {
+            self.history_entries()
+        } else if self.tree.enabled {
+            let nodes = tree::flatten(&self.inner_paths, &self.tree);
+            tree::filter(&nodes, &self.user_input)
+        } else {
+            file::filter_file_data(Cow::Borrowed(&self.inner_paths), &self.user_input)
+        };
+
+        if self.selected_index >= self.displayed_paths.len() {
+            self.selected_index = self.displayed_paths.len().saturating_sub(1);
+        }
+    }
+
+    pub fn handle_user_input_change(&mut self, to_push: char) {
+        self.user_input.push(to_push);
+        self.refresh_displayed_paths();
+    }
+
+    pub fn handle_backspace(&mut self) {
+        self.user_input.pop();
+        self.refresh_displayed_paths();
+    }
+
+    pub fn toggle_tree_mode(&mut self) {
+        self.tree.enabled = !self.tree.enabled;
+        self.refresh_displayed_paths();
+    }
+
+    /// Expands the selected directory inline, or opens the selected file.
+    pub fn handle_tree_expand(&mut self) {
+        let selected = match self.displayed_paths.get(self.selected_index) {
+            Some(selected) => selected.clone(),
+            None => return,
+        };
+
+        if !selected.is_dir {
+            self.handle_enter();
+            return;
+        }
+
+        self.tree.expand(&selected.absolute);
+        self.refresh_displayed_paths();
+    }
+
+    /// Collapses the selected directory. When it is not expanded, collapses its parent and
+    /// selects it instead; top level entries move back one directory.
+    pub fn handle_tree_collapse(&mut self) {
+        let selected = match self.displayed_paths.get(self.selected_index) {
+            Some(selected) => selected.clone(),
+            None => return self.handle_move_back(),
+        };
+
+        let target = if self.tree.is_expanded(&selected.absolute) {
+            selected.absolute
+        } else if let Some(parent) = selected.parent {
+            parent
+        } else {
+            return self.handle_move_back();
+        };
+
+        self.tree.collapse(&target);
+        self.refresh_displayed_paths();
+        if let Some(index) = self
+            .displayed_paths
+            .iter()
+            .position(|fd| fd.absolute == target)
+        {
+            self.selected_index = index;
+        }
+    }
+
+    pub fn update_selected_index(&mut self, action: KeyCode) {
+        if self.list_len() == 0 {
+            return;
+        }
+
+        let mut updated_index = 0;
+        let total_dirs = self.list_len() - 1;
+
+        if action == KeyCode::Down {
+            if self.selected_index == total_dirs {
+                updated_index = 0;
+            } else {
+                updated_index = self.selected_index + 1;
+            }
+        } else if action == KeyCode::Up {
+            if self.selected_index == 0 {
+                updated_index = total_dirs
+            } else {
+                updated_index = self.selected_index - 1;
+            }
+        }
+
+        self.selected_index = updated_index;
+    }
+
+    /// Adds a digit to the count typed before a command, as in `5j`.
+    pub fn push_count_digit(&mut self, digit: u32) {
+        let count = self.count.unwrap_or(0);
+        self.count = Some(count.saturating_mul(10).saturating_add(digit as usize));
+    }
+
+    pub fn take_count(&mut self) -> Option<usize> {
+        self.count.take()
+    }
+
+    fn select_clamped(&mut self, index: usize) {
+        self.selected_index = cmp::min(index, self.list_len().saturating_sub(1));
+    }
+
+    /// Moves the selection one entry, wrapping around the ends, or `count` entries without
+    /// wrapping.
+    pub fn move_selection(&mut self, action: KeyCode, count: Option<usize>) {
+        match (action, count) {
+            (_, None) => self.update_selected_index(action),
+            (KeyCode::Down, Some(count)) => {
+                self.select_clamped(self.selected_index.saturating_add(count))
+            }
+            (KeyCode::Up, Some(count)) => {
+                self.selected_index = self.selected_index.saturating_sub(count)
+            }
+            _ => {}
+        }
+    }
+
+    /// `gg` and `G`: the entry numbered `count` when given, otherwise the first or last one.
+    pub fn handle_goto(&mut self, count: Option<usize>, last: bool) {
+        match count {
+            Some(index) => self.select_clamped(index),
+            None if last => self.select_clamped(usize::MAX),
+            None => self.selected_index = 0,
+        }
+    }
+
+    /// `H`, `M` and `L`: the top, middle or bottom entry on screen.
+    pub fn handle_screen_position(&mut self, position: char, count: Option<usize>) {
+        let len = self.list_len();
+        let rows = if self.list_rows == 0 {
+            len
+        } else {
+            self.list_rows
+        };
+        let last_visible = cmp::min(self.scroll_offset + rows, len).saturating_sub(1);
+        let offset = count.unwrap_or(1).saturating_sub(1);
+
+        let index = match position {
+            'H' => cmp::min(self.scroll_offset + offset, last_visible),
+            'L' => cmp::max(last_visible.saturating_sub(offset), self.scroll_offset),
+            _ => self.scroll_offset + (last_visible - self.scroll_offset.min(last_visible)) / 2,
+        };
+        self.select_clamped(index);
+    }
+
+    /// `}` and `{`: the first entry of the next group of directories or files, or of the
+    /// current group when the selection is inside it.
+    pub fn handle_group_jump(&mut self, forward: bool, count: Option<usize>) {
+        let paths = &self.displayed_paths;
+        let starts_group = |i: usize| i == 0 || paths[i].is_dir != paths[i - 1].is_dir;
+        let mut index = self.selected_index;
+
+        for _ in 0..count.unwrap_or(1) {
+            if forward {
+                index = (index + 1..paths.len())
+                    .find(|i| starts_group(*i))
+                    .unwrap_or(paths.len().saturating_sub(1));
+            } else {
+                index = (0..index).rev().find(|i| starts_group(*i)).unwrap_or(0);
+            }
+        }
+        self.select_clamped(index);
+    }
+
+    fn search_prompt(&self) -> String {
+        if self.search.select {
+            return format!("select:{}", self.search.input);
+        }
+        let direction = if self.search.forward { '/' } else { '?' };
+        format!("{}{}", direction, self.search.input)
+    }
+
+    /// Opens the `/` (forward) or `?` (backward) prompt. The selection follows the first
+    /// match while typing and the list is not filtered.
+    pub fn start_search(&mut self, forward: bool) {
+        self.search.start(forward, self.selected_index);
+        self.app_mode = AppMode::Search;
+        self.message = "".to_owned();
+    }
+
+    fn search_from_origin(&mut self) {
+        if self.search.select {
+            return;
+        }
+        let pattern = match self.search.highlight(true) {
+            Some(pattern) => pattern,
+            None => {
+                self.selected_index = self.search.origin;
+                return;
+            }
+        };
+
+        self.selected_index = search::find_match(
+            &self.displayed_paths,
+            &pattern,
+            self.search.origin,
+            self.search.forward,
+        )
+        .unwrap_or(self.search.origin);
+    }
+
+    pub fn handle_search_input(&mut self, c: char) {
+        self.search.input.push(c);
+        self.search_from_origin();
+    }
+
+    pub fn handle_search_backspace(&mut self) {
+        if self.search.input.pop().is_none() {
+            self.cancel_search();
+            return;
+        }
+        self.search_from_origin();
+    }
+
+    pub fn handle_search_history(&mut self, older: bool) {
+        self.search.browse_history(older);
+        self.search_from_origin();
+    }
+
+    pub fn cancel_search(&mut self) {
+        self.search.select = false;
+        self.selected_index = self.search.origin;
+        self.app_mode = AppMode::FileExplorer;
+    }
+
+    pub fn confirm_search(&mut self) {
+        self.app_mode = AppMode::FileExplorer;
+        if self.search.select {
+            self.confirm_select_pattern();
+            return;
+        }
+
+        // An empty search repeats the previous pattern in the new direction, like vim.
+        if self.search.input.is_empty() {
+            self.repeat_search(false, None);
+            return;
+        }
+
+        self.search.confirm();
+        let pattern = grep::Pattern::new(&self.search.input);
+        match search::find_match(
+            &self.displayed_paths,
+            &pattern,
+            self.search.origin,
+            self.search.forward,
+        ) {
+            Some(index) => self.selected_index = index,
+            None => {
+                self.selected_index = self.search.origin;
+                self.message = format!("Pattern not found: {}", self.search.input);
+            }
+        }
+    }
+
+    /// `n` and `N`: the next match in the search direction, or the opposite one when
+    /// `reverse` is set.
+    pub fn repeat_search(&mut self, reverse: bool, count: Option<usize>) {
+        let pattern = match &self.search.pattern {
+            Some(pattern) => pattern.clone(),
+            None => {
+                self.message = "No previous search".to_owned();
+                return;
+            }
+        };
+
+        let forward = self.search.forward != reverse;
+        for _ in 0..count.unwrap_or(1) {
+            match search::find_match(
+                &self.displayed_paths,
+                &pattern,
+                self.selected_index,
+                forward,
+            ) {
+                Some(index) => self.selected_index = index,
+                None => {
+                    self.message = "Pattern not found".to_owned();
+                    return;
+                }
+            }
+        }
+    }
+
+    /// `*`: the next file with the same extension as the selected one.
+    pub fn handle_same_extension(&mut self, count: Option<usize>) {
+        let extension = match self.displayed_paths.get(self.selected_index) {
+            Some(fd) => layout::split_extension(&fd.shortname).1.to_owned(),
+            None => return,
+        };
+
+        for _ in 0..count.unwrap_or(1) {
+            match search::find_same_extension(&self.displayed_paths, self.selected_index, true) {
+                Some(index) => self.selected_index = index,
+                None => {
+                    self.message = "No other entry with the same extension".to_owned();
+                    return;
+                }
+            }
+        }
+        self.search.pattern = Some(grep::Pattern::new(&extension));
+        self.search.forward = true;
+    }
+
+    /// Stops highlighting the last search, like `:nohlsearch`.
+    pub fn clear_search_highlight(&mut self) {
+        self.search.pattern = None;
+    }
+
+    /// Toggles the selection of `count` entries starting at the cursor, then moves past them.
+    pub fn handle_toggle_selection(&mut self, count: usize) {
+        if self.displayed_paths.is_empty() {
+            return;
+        }
+
+        let end = cmp::min(self.selected_index + count, self.displayed_paths.len());
+        for fd in &self.displayed_paths[self.selected_index..end] {
+            self.selection.toggle(&fd.absolute);
+        }
+        self.select_clamped(end);
+    }
+
+    pub fn handle_select_all(&mut self) {
+        self.selection.select_all(&self.displayed_paths);
+    }
+
+    pub fn handle_invert_selection(&mut self) {
+        self.selection.invert(&self.displayed_paths);
+    }
+
+    pub fn handle_clear_selection(&mut self) {
+        self.selection.clear();
+        self.message = "Selection cleared".to_owned();
+    }
+
+    /// Opens a prompt for a glob or substring; confirming it selects the displayed entries
+    /// whose names match.
+    pub fn start_select_pattern(&mut self) {
+        self.search.start(true, self.selected_index);
+        self.search.select = true;
+        self.app_mode = AppMode::Search;
+        self.message = "".to_owned();
+    }
+
+    fn confirm_select_pattern(&mut self) {
+        self.search.select = false;
+        if self.search.input.is_empty() {
+            return;
+        }
+        let count = self
+            .selection
+            .select_matching(&self.displayed_paths, &self.search.input);
+        self.message = format!("Selected {} entries matching {}", count, self.search.input);
+    }
+
+    /// `Space` in visual mode: adds the range to the selection.
+    pub fn handle_visual_select(&mut self) {
+        for path in self.visual_paths() {
+            self.selection.insert(&path);
+        }
+        self.selected_index = self.visual_range().map_or(self.selected_index, |r| r.0);
+        self.exit_visual_mode();
+    }
+
+    /// The inclusive range of entries covered by visual mode.
+    pub fn visual_range(&self) -> Option<(usize, usize)> {
+        if self.keybind_mode != KeybindMode::Visual || self.displayed_paths.is_empty() {
+            return None;
+        }
+        let last = self.displayed_paths.len() - 1;
+        let anchor = cmp::min(self.visual_anchor, last);
+        let cursor = cmp::min(self.selected_index, last);
+        Some((cmp::min(anchor, cursor), cmp::max(anchor, cursor)))
+    }
+
+    pub fn start_visual_mode(&mut self, whole_groups: bool) {
+        if self.displayed_paths.is_empty() {
+            return;
+        }
+        self.keybind_mode = KeybindMode::Visual;
+        self.visual_anchor = self.selected_index;
+        if whole_groups {
+            self.extend_visual_to_groups();
+        }
+    }
+
+    pub fn exit_visual_mode(&mut self) {
+        self.keybind_mode = KeybindMode::Normal;
+    }
+
+    fn group_bounds(&self, index: usize) -> (usize, usize) {
+        let paths = &self.displayed_paths;
+        let is_dir = paths[index].is_dir;
+        let start = (0..index)
+            .rev()
+            .take_while(|i| paths[*i].is_dir == is_dir)
+            .last()
+            .unwrap_or(index);
+        let end = (index + 1..paths.len())
+            .take_while(|i| paths[*i].is_dir == is_dir)
+            .last()
+            .unwrap_or(index);
+        (start, end)
+    }
+
+    /// `V`: grows the visual range so that it covers whole groups of directories or files.
+    pub fn extend_visual_to_groups(&mut self) {
+        let (start, end) = match self.visual_range() {
+            Some(range) => range,
+            None => return,
+        };
+        let (group_start, _) = self.group_bounds(start);
+        let (_, group_end) = self.group_bounds(end);
+
+        if self.selected_index >= self.visual_anchor {
+            self.visual_anchor = group_start;
+            self.selected_index = group_end;
+        } else {
+            self.visual_anchor = group_end;
+            self.selected_index = group_start;
+        }
+    }
+
+    fn visual_paths(&self) -> Vec<String> {
+        match self.visual_range() {
+            Some((start, end)) => self.displayed_paths[start..=end]
+                .iter()
+                .map(|fd| fd.absolute.clone())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// `d` in visual mode, or `Delete` as in normal mode: marks every entry in the range for
+    /// deletion.
+    pub fn handle_visual_delete(&mut self) {
+        if let Some((start, end)) = self.visual_range() {
+            for fd in &self.displayed_paths[start..=end] {
+                self.marked.insert(&fd.absolute);
+            }
+            self.message = format!(
+                "{} entries marked for deletion, press Shift + D to review",
+                self.marked.len()
+            );
+        }
+        self.selected_index = self.visual_range().map_or(self.selected_index, |r| r.0);
+        self.exit_visual_mode();
+    }
+
+    /// `y` and `x` in visual mode: stores the range in the register for a later paste.
+    pub fn handle_visual_yank(&mut self, cut: bool) {
+        let paths = self.visual_paths();
+        let verb = if cut { "Cut" } else { "Yanked" };
+        self.message = format!("{} {} entries", verb, paths.len());
+        self.register.store(paths, cut);
+        self.selected_index = self.visual_range().map_or(self.selected_index, |r| r.0);
+        self.exit_visual_mode();
+    }
+
+    /// `yy`, `dd` and `x`: stores the selection, or `count` entries starting at the selected
+    /// one, in the register for a later paste.
+    pub fn handle_yank(&mut self, count: usize, cut: bool) {
+        if self.app_mode != AppMode::FileExplorer {
+            return;
+        }
+        let paths: Vec<String> = if self.selection.is_empty() {
+            let end = cmp::min(self.selected_index + count, self.displayed_paths.len());
+            self.displayed_paths[cmp::min(self.selected_index, end)..end]
+                .iter()
+                .map(|fd| fd.absolute.clone())
+                .collect()
+        } else {
+            self.selection.paths()
+        };
+        if paths.is_empty() {
+            return;
+        }
+
+        let verb = if cut { "Cut" } else { "Yanked" };
+        self.message = format!("{} {} entries", verb, paths.len());
+        self.register.store(paths, cut);
+    }
+
+    /// `p`: copies the yanked entries into the current directory, or moves the cut ones.
+    pub fn handle_paste(&mut self) {
+        if self.app_mode != AppMode::FileExplorer {
+            return;
+        }
+        if self.register.is_empty() {
+            self.message = "Nothing to paste".to_owned();
+            return;
+        }
 
-                stdout.queue(cursor::MoveTo(0, 0))?;
-                print!(
-                    ".{}{}/",
-                    SetAttribute(Attribute::Bold),
-                    self.curr_absolute_path
-                );
+        self.paste = register::Paste::new(&self.register, &self.curr_absolute_path);
+        self.continue_paste(None);
+    }
 
-                print!("{}", self.user_input);
-                if self.keybind_mode == KeybindMode::Normal {
-                    stdout.queue(cursor::MoveTo(0, (self.selected_index + 1) as u16))?;
-                }
-                stdout.flush()?;
-            }
-            AppMode::Command => {
-                print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
-                let mut stdout = stdout();
-                stdout.queue(cursor::MoveTo(0, 0))?;
-                print!(
-                    "{}{}",
-                    SetAttribute(Attribute::Bold),
-                    self.curr_absolute_path
+    fn continue_paste(&mut self, choice: Option<register::Conflict>) {
+        match self.paste.resume(choice) {
+            Some(name) => {
+                self.keybind_mode = KeybindMode::Conflict;
+                self.message = format!(
+                    "{} already exists: [o]verwrite, [s]kip, [r]ename, [O, S, R] for all, [Esc] stop",
+                    name
                 );
-                stdout.queue(cursor::MoveTo(0, 2))?;
-                let msg_split: Vec<&str> = self.message.split("\n").collect();
-                if msg_split.len() > 1 {
-                    for line in msg_split {
-                        println!("{}", line);
-                        stdout.queue(cursor::MoveToColumn(0))?;
-                    }
-                } else {
-                    print!("{}", self.message);
-                }
-                stdout.queue(cursor::MoveTo(0, 1))?;
-                print!("{}{}{} ", SetForegroundColor(styles::ERR), ">", ResetColor);
-                print!("{}", self.user_input);
-                stdout.flush()?;
             }
+            None => self.finish_paste(),
         }
-        Ok(())
     }
 
-    pub fn handle_user_input_change(&mut self, to_push: char) {
-        self.user_input.push(to_push);
-        self.displayed_paths =
-            file::filter_file_data(Cow::Borrowed(&self.inner_paths), &self.user_input);
+    /// Settles the name conflict the paste stopped at, and every later one with `for_all`.
+    pub fn resolve_conflict(&mut self, conflict: register::Conflict, for_all: bool) {
+        if for_all {
+            self.paste.for_all = Some(conflict);
+        }
+        self.keybind_mode = KeybindMode::Normal;
+        self.continue_paste(Some(conflict));
     }
 
-    pub fn handle_backspace(&mut self) {
-        self.user_input.pop();
-        self.displayed_paths =
-            file::filter_file_data(Cow::Borrowed(&self.inner_paths), &self.user_input);
+    /// Stops the paste at the conflict, keeping what was pasted so far.
+    pub fn cancel_paste(&mut self) {
+        self.paste.pending.clear();
+        self.finish_paste();
     }
 
-    pub fn update_selected_index(&mut self, action: KeyCode) {
-        let mut updated_index = 0;
-        let total_dirs = self.displayed_paths.len() - 1;
-
-        if action == KeyCode::Down {
-            if self.selected_index == total_dirs {
-                updated_index = 0;
-            } else {
-                updated_index = self.selected_index + 1;
-            }
-        } else if action == KeyCode::Up {
-            if self.selected_index == 0 {
-                updated_index = total_dirs
-            } else {
-                updated_index = self.selected_index - 1;
+    fn finish_paste(&mut self) {
+        let paste = std::mem::take(&mut self.paste);
+        self.keybind_mode = KeybindMode::Normal;
+        for operation in &paste.operations {
+            if let journal::Operation::Rename { from, to } = operation {
+                self.follow_rename(from, to);
             }
         }
+        // Whatever was moved is gone from where it was cut.
+        if paste.cut {
+            self.register
+                .paths
+                .retain(|path| Path::new(path).symlink_metadata().is_ok());
+        }
 
-        self.selected_index = updated_index;
+        let verb = if paste.cut { "Moved" } else { "Pasted" };
+        let description = format!("{} {} entries", verb, paste.pasted);
+        self.journal.record(&description, paste.operations);
+        self.update_paths();
+        self.message = description;
+
+        if !paste.failures.is_empty() {
+            self.dialog = ConfirmDialog::new(
+                &format!("{}, {} failed", self.message, paste.failures.len()),
+                paste.failures,
+                None,
+                DialogAction::Report,
+            );
+            self.keybind_mode = KeybindMode::Dialog;
+        }
+    }
+
+    /// `:` in visual mode: opens the command prompt with the range appended to the command.
+    pub fn handle_visual_command(&mut self) {
+        let targets = self.visual_paths();
+        self.exit_visual_mode();
+        self.toggle_command_mode();
+        self.keybind_mode = KeybindMode::Insert;
+        self.command_targets = targets;
     }
 
     pub fn handle_move_back(&mut self) {
@@ -161,10 +1658,9 @@ impl AppState {
         match metadata_res {
             Ok(metadata) => {
                 if metadata.is_file() {
-                    if let Err(e) = Command::new("nvim").arg(&selected.shortname).status() {
-                        self.message = e.to_string();
-                        return;
-                    }
+                    let path = selected.absolute.clone();
+                    self.open_in_editor(&path, None);
+                    return;
                 }
             }
             Err(e) => {
@@ -183,7 +1679,8 @@ impl AppState {
 
     fn handle_enter_command(&mut self) {
         let split: Vec<&str> = self.user_input.split(" ").collect();
-        let args: Vec<&str> = split[1..].iter().map(|x| x.to_owned()).collect();
+        let mut args: Vec<&str> = split[1..].iter().map(|x| x.to_owned()).collect();
+        args.extend(self.command_targets.iter().map(|target| target.as_str()));
         let cmd_res = Command::new(split[0])
             .args(args)
             .stdout(std::process::Stdio::piped())
@@ -210,10 +1707,20 @@ impl AppState {
         match self.app_mode {
             AppMode::FileExplorer => self.handle_enter_explorer(),
             AppMode::Command => self.handle_enter_command(),
+            AppMode::Find | AppMode::IndexSearch | AppMode::Jump => self.handle_enter_find(),
+            AppMode::Bookmarks => self.handle_enter_bookmark(),
+            AppMode::Trash => self.handle_restore_trashed(),
+            AppMode::History => {}
+            AppMode::Search => self.confirm_search(),
+            AppMode::Grep => self.handle_enter_grep(),
         }
     }
 
     pub fn handle_create(&mut self) {
+        let path = Path::new(&self.curr_absolute_path)
+            .join(self.user_input.trim_end_matches('/'))
+            .to_string_lossy()
+            .into_owned();
         if self.user_input.contains("/") {
             if let Err(e) = fs::create_dir(&self.user_input) {
                 self.message = e.to_string();
@@ -225,49 +1732,400 @@ impl AppState {
                 return;
             }
         }
+        if Path::new(&path).exists() {
+            let description = format!("Create {}", config::abbreviate_home(&path));
+            let operation = journal::Operation::Create {
+                path,
+                trashed: None,
+            };
+            self.journal.record(&description, vec![operation]);
+        }
 
         self.update_paths();
         self.message = String::from("File successfully created");
     }
 
-    pub fn handle_mark_delete(&mut self) {
-        self.displayed_paths[self.selected_index].toggle_mark_for_deletion();
-        let marked_files: Vec<&str> = self
+    /// Starts editing the name of the selected entry in place. With `keep_extension` only the
+    /// part before the extension of a file is edited.
+    pub fn start_rename(&mut self, keep_extension: bool) {
+        let fd = match self.displayed_paths.get(self.selected_index) {
+            Some(fd) if self.app_mode == AppMode::FileExplorer => fd,
+            _ => return,
+        };
+
+        let (stem, extension) = if keep_extension && !fd.is_dir {
+            layout::split_extension(&fd.shortname)
+        } else {
+            (fd.shortname.as_str(), "")
+        };
+        self.rename = rename::InlineRename {
+            target: fd.absolute.clone(),
+            editor: rename::LineEditor::new(stem),
+            suffix: extension.to_owned(),
+        };
+        self.keybind_mode = KeybindMode::Rename;
+        self.message = "".to_owned();
+    }
+
+    pub fn cancel_rename(&mut self) {
+        self.rename = rename::InlineRename::default();
+        self.keybind_mode = KeybindMode::Normal;
+    }
+
+    /// Renames the entry to the edited name. An invalid or taken name keeps the editor open.
+    pub fn confirm_rename(&mut self) {
+        let target = Path::new(&self.rename.target);
+        let (dir, original) = match (target.parent(), target.file_name()) {
+            (Some(dir), Some(original)) => (dir, original.to_string_lossy()),
+            _ => return self.cancel_rename(),
+        };
+        let name = self.rename.new_name();
+        if let Err(e) = rename::validate_name(dir, &original, &name) {
+            self.message = e;
+            return;
+        }
+        if name == original {
+            return self.cancel_rename();
+        }
+
+        let renamed = dir.join(&name).to_string_lossy().into_owned();
+        if let Err(e) = fs::rename(target, &renamed) {
+            self.message = format!("Could not rename {}: {}", original, e);
+            return;
+        }
+        let description = format!("Rename {} to {}", original, name);
+        self.journal.record(
+            &description,
+            vec![journal::Operation::Rename {
+                from: self.rename.target.clone(),
+                to: renamed.clone(),
+            }],
+        );
+        self.follow_rename(&self.rename.target.clone(), &renamed);
+
+        self.cancel_rename();
+        self.update_paths();
+        if let Some(index) = self
             .displayed_paths
             .iter()
-            .filter(|fd| fd.marked_for_deletion)
-            .map(|fd| fd.shortname.as_str())
-            .collect();
+            .position(|fd| fd.absolute == renamed)
+        {
+            self.selected_index = index;
+        }
+        self.message = description;
+    }
+
+    /// Keeps the selection and the deletion marks on an entry that was renamed or moved.
+    fn follow_rename(&mut self, from: &str, to: &str) {
+        for set in [&mut self.selection, &mut self.marked] {
+            if set.contains(from) {
+                set.remove(from);
+                set.insert(to);
+            }
+        }
+    }
+
+    /// Opens the names of the selected entries (or of every listed entry) in the editor and
+    /// previews the renames made there.
+    pub fn start_bulk_rename(&mut self) {
+        if self.app_mode != AppMode::FileExplorer {
+            return;
+        }
+        let paths = if self.selection.is_empty() {
+            self.displayed_paths
+                .iter()
+                .map(|fd| fd.absolute.clone())
+                .collect()
+        } else {
+            self.selection.paths()
+        };
+        if paths.is_empty() {
+            self.message = "Nothing to rename".to_owned();
+            return;
+        }
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.subsec_nanos());
+        let file = env::temp_dir().join(format!(
+            "slingshot-rename-{}-{}.txt",
+            std::process::id(),
+            nanos
+        ));
+        if let Err(e) = rename::write_names_file(&file, &paths) {
+            self.message = format!("Could not write {}: {}", file.display(), e);
+            return;
+        }
+        self.open_in_editor(&file.to_string_lossy(), None);
+        let edited = fs::read_to_string(&file);
+        let _ = fs::remove_file(&file);
+        match edited {
+            Ok(edited) => self.review_bulk_rename(&paths, &edited),
+            Err(e) => self.message = format!("Could not read {}: {}", file.display(), e),
+        }
+    }
+
+    /// Shows what renaming `paths` to the `edited` names would do before anything is renamed.
+    pub fn review_bulk_rename(&mut self, paths: &[String], edited: &str) {
+        let plan = match rename::BulkRename::plan(paths, edited) {
+            Ok(plan) => plan,
+            Err(e) => {
+                self.message = format!("Nothing renamed: {}", e);
+                return;
+            }
+        };
+        if plan.renames.is_empty() {
+            self.message = "No names changed".to_owned();
+            return;
+        }
+
+        let title = format!("Rename {} entries?", plan.renames.len());
+        self.dialog = ConfirmDialog::new(&title, plan.lines(), None, DialogAction::BulkRename);
+        self.bulk_rename = plan;
+        self.keybind_mode = KeybindMode::Dialog;
+    }
+
+    /// Carries out the previewed renames. A failing step stops the rest, and whatever went
+    /// through can be undone as a whole.
+    fn apply_bulk_rename(&mut self) {
+        let plan = std::mem::take(&mut self.bulk_rename);
+        let mut operations = Vec::new();
+        let mut error = None;
+        for (from, to) in &plan.steps {
+            if let Err(e) = fs::rename(from, to) {
+                error = Some(format!("Could not rename {}: {}", from, e));
+                break;
+            }
+            operations.push(journal::Operation::Rename {
+                from: from.clone(),
+                to: to.clone(),
+            });
+            self.follow_rename(from, to);
+        }
+
+        let description = format!("Rename {} entries", plan.renames.len());
+        self.journal.record(&description, operations);
+        self.update_paths();
+        self.message = match error {
+            Some(error) => format!("{}; undo to revert the renames that went through", error),
+            None => description,
+        };
+    }
+
+    pub fn handle_undo(&mut self) {
+        self.message = self.journal.undo();
+        self.update_paths_after_journal();
+    }
+
+    pub fn handle_redo(&mut self) {
+        self.message = self.journal.redo();
+        self.update_paths_after_journal();
+    }
+
+    /// Undo and redo can change the current directory behind the list's back.
+    fn update_paths_after_journal(&mut self) {
+        let paths = file::get_paths(&self.curr_absolute_path);
+        if let Ok(paths) = file::generate_file_data(paths) {
+            self.inner_paths = paths;
+        }
+        self.refresh_displayed_paths();
+    }
+
+    pub fn start_history_mode(&mut self) {
+        if self.journal.history().is_empty() {
+            self.message = "No changes to undo yet".to_owned();
+            return;
+        }
+
+        self.remember_dir_state();
+        self.app_mode = AppMode::History;
+        self.keybind_mode = KeybindMode::Normal;
+        self.user_input = "".to_owned();
+        self.message = "".to_owned();
+        self.selected_index = 0;
+        self.refresh_displayed_paths();
+    }
+
+    fn history_entries(&self) -> Vec<file::FileData> {
+        self.journal
+            .history()
+            .iter()
+            .map(|line| file::file_data_for("", line, false))
+            .collect()
+    }
+
+    /// Toggles the deletion mark of `count` entries starting at the selection. With a
+    /// selection, the selected entries are reviewed for deletion instead.
+    pub fn handle_mark_delete(&mut self, count: usize) {
+        if !self.selection.is_empty() {
+            self.start_deletion_review(false);
+            return;
+        }
+        if self.displayed_paths.is_empty() {
+            return;
+        }
+
+        let end = cmp::min(self.selected_index + count, self.displayed_paths.len());
+        for fd in &self.displayed_paths[self.selected_index..end] {
+            self.marked.toggle(&fd.absolute);
+        }
+        if count > 1 {
+            self.select_clamped(end);
+        }
 
         self.message = format!(
-            "Press Ctrl + Y to confirm deletion of files: {:?}",
-            marked_files
+            "{} entries marked for deletion, press Shift + D to review",
+            self.marked.len()
         );
         return;
     }
 
-    pub fn handle_confirm_delete(&mut self) {
-        for path in &self.displayed_paths {
-            if path.marked_for_deletion {
-                if let Err(e) = fs::remove_file(&path.absolute) {
-                    self.message = e.to_string();
-                    return;
+    /// Every entry marked for deletion or selected, wherever it is listed.
+    fn deletion_targets(&self) -> Vec<String> {
+        let mut targets = self.marked.clone();
+        for path in self.selection.paths() {
+            targets.insert(&path);
+        }
+        targets.paths()
+    }
+
+    /// Opens the review listing every pending deletion, which has to be confirmed before
+    /// anything is moved to the trash, or deleted for good when `permanent` is set.
+    pub fn start_deletion_review(&mut self, permanent: bool) {
+        let targets = self.deletion_targets();
+        if targets.is_empty() {
+            self.message = "Nothing is marked for deletion".to_owned();
+            return;
+        }
+
+        self.deletion = deletion::Review::new(&targets, permanent);
+        let typed =
+            Some(deletion::CONFIRMATION).filter(|_| self.deletion.needs_typed_confirmation());
+        let title = if permanent {
+            format!("Permanently delete {} entries?", targets.len())
+        } else {
+            format!("Move {} entries to the trash?", targets.len())
+        };
+        self.dialog =
+            ConfirmDialog::new(&title, self.deletion.lines(), typed, DialogAction::Delete);
+        self.keybind_mode = KeybindMode::Dialog;
+    }
+
+    /// Closes the dialog without running its action.
+    pub fn cancel_dialog(&mut self) {
+        self.keybind_mode = KeybindMode::Normal;
+        match self.dialog.action {
+            DialogAction::Report => {}
+            DialogAction::BulkRename => {
+                self.bulk_rename = rename::BulkRename::default();
+                self.message = "Rename cancelled".to_owned();
+            }
+            DialogAction::Delete | DialogAction::PurgeTrashed | DialogAction::EmptyTrash => {
+                self.deletion = deletion::Review::default();
+                self.message = "Deletion cancelled".to_owned();
+            }
+        }
+    }
+
+    /// Runs the action of the dialog once it is confirmed.
+    pub fn confirm_dialog(&mut self) {
+        if !self.dialog.accepts() {
+            return;
+        }
+        self.keybind_mode = KeybindMode::Normal;
+
+        match self.dialog.action {
+            DialogAction::Delete => self.delete_reviewed(),
+            DialogAction::PurgeTrashed => self.purge_trashed(),
+            DialogAction::EmptyTrash => self.empty_trash(),
+            DialogAction::BulkRename => self.apply_bulk_rename(),
+            DialogAction::Report => {}
+        }
+    }
+
+    /// Trashes or deletes every reviewed entry, carrying on past failures. Whatever could not
+    /// be deleted is listed in a report afterwards.
+    fn delete_reviewed(&mut self) {
+        let review = std::mem::take(&mut self.deletion);
+        let mut failures: Vec<(String, String)> = Vec::new();
+        let mut operations = Vec::new();
+        for entry in &review.entries {
+            let entry_failures = match entry.kind {
+                deletion::Kind::Missing => continue,
+                _ if !review.permanent => match trash::trash_path(&entry.path) {
+                    Ok(trashed) => {
+                        operations.push(journal::Operation::Trash {
+                            original: entry.path.clone(),
+                            trashed: trashed.to_string_lossy().into_owned(),
+                        });
+                        Vec::new()
+                    }
+                    Err(e) => vec![(entry.path.clone(), e.to_string())],
+                },
+                _ => {
+                    let entry_failures = deletion::remove_all(Path::new(&entry.path));
+                    if entry_failures.is_empty() {
+                        operations.push(journal::Operation::Delete {
+                            path: entry.path.clone(),
+                        });
+                    }
+                    entry_failures
                 }
+            };
+            if entry_failures.is_empty() {
+                self.selection.remove(&entry.path);
+                self.marked.remove(&entry.path);
             }
+            failures.extend(entry_failures);
         }
 
         self.update_paths();
-        self.message = String::from("Files successfully removed");
+        self.message = if review.permanent {
+            format!("Deleted {} entries", operations.len())
+        } else {
+            format!("Moved {} entries to the trash", operations.len())
+        };
+        self.journal.record(&self.message.clone(), operations);
+
+        if !failures.is_empty() {
+            let lines = failures
+                .iter()
+                .map(|(path, error)| format!("{}: {}", config::abbreviate_home(path), error))
+                .collect();
+            self.dialog = ConfirmDialog::new(
+                &format!("{}, {} could not be deleted", self.message, failures.len()),
+                lines,
+                None,
+                DialogAction::Report,
+            );
+            self.keybind_mode = KeybindMode::Dialog;
+        }
     }
 
     pub fn toggle_command_mode(&mut self) {
         self.user_input = String::from("");
         self.message = String::from("");
+        self.command_targets.clear();
         self.update_paths();
 
         match self.app_mode {
             AppMode::FileExplorer => self.app_mode = AppMode::Command,
             AppMode::Command => self.app_mode = AppMode::FileExplorer,
+            AppMode::Find
+            | AppMode::Grep
+            | AppMode::IndexSearch
+            | AppMode::Jump
+            | AppMode::Bookmarks
+            | AppMode::Search
+            | AppMode::Trash
+            | AppMode::History => {
+                self.finder.cancel();
+                self.grep.cancel();
+                self.app_mode = AppMode::Command;
+            }
+        }
+        if self.app_mode == AppMode::Command {
+            self.command_targets = self.selection.paths();
         }
     }
 
@@ -280,28 +2138,225 @@ impl AppState {
         let updated_file_data_res = file::generate_file_data(paths);
         if let Ok(value) = updated_file_data_res {
             self.user_input = "".to_owned();
-            self.inner_paths = value.clone();
-            self.displayed_paths = value.clone();
+            self.inner_paths = value;
+            self.refresh_displayed_paths();
         } else if let Err(e) = updated_file_data_res {
             self.message = e.to_string();
             return;
         }
     }
 
+    fn current_position(&self) -> jumplist::Position {
+        jumplist::Position {
+            dir: self.curr_absolute_path.clone(),
+            selected: self
+                .displayed_paths
+                .get(self.selected_index)
+                .map(|fd| fd.absolute.clone()),
+            filter: if self.app_mode == AppMode::FileExplorer {
+                self.user_input.clone()
+            } else {
+                "".to_owned()
+            },
+        }
+    }
+
+    /// Moves back to `position`, restoring its filter and selected entry.
+    fn restore_position(&mut self, position: &jumplist::Position) -> bool {
+        if let Err(e) = std::env::set_current_dir(&position.dir) {
+            self.message = e.to_string();
+            return false;
+        }
+
+        self.exit_find_mode();
+        if !self.load_dir(&position.dir) {
+            return false;
+        }
+        self.user_input = position.filter.clone();
+        self.refresh_displayed_paths();
+        if let Some(index) = self
+            .displayed_paths
+            .iter()
+            .position(|fd| Some(&fd.absolute) == position.selected.as_ref())
+        {
+            self.selected_index = index;
+        }
+        true
+    }
+
+    pub fn handle_jump_back(&mut self) {
+        match self.jump_list.back(self.current_position()) {
+            Some(position) => {
+                if !self.restore_position(&position) {
+                    self.jump_list.forward(position);
+                }
+            }
+            None => self.message = "Already at the oldest position".to_owned(),
+        }
+    }
+
+    pub fn handle_jump_forward(&mut self) {
+        match self.jump_list.forward(self.current_position()) {
+            Some(position) => {
+                if !self.restore_position(&position) {
+                    self.jump_list.back(position);
+                }
+            }
+            None => self.message = "Already at the newest position".to_owned(),
+        }
+    }
+
+    /// Returns to the position before the latest jump, like `''` in vim.
+    pub fn handle_jump_previous(&mut self) {
+        let previous = match self.jump_list.previous.clone() {
+            Some(previous) => previous,
+            None => {
+                self.message = "No previous position".to_owned();
+                return;
+            }
+        };
+
+        let from = self.current_position();
+        if self.restore_position(&previous) {
+            self.jump_list.push(from);
+        }
+    }
+
+    /// Caches the selected entry, scroll offset and filter of the current directory.
+    fn remember_dir_state(&mut self) {
+        if self.app_mode != AppMode::FileExplorer || self.curr_absolute_path.is_empty() {
+            return;
+        }
+
+        let selected = self
+            .displayed_paths
+            .get(self.selected_index)
+            .and_then(|fd| Path::new(&fd.absolute).file_name())
+            .map(|name| name.to_string_lossy().into_owned());
+        let state = dirstate::DirState::new(selected, self.scroll_offset, &self.user_input);
+
+        if self.config.persist_dir_state {
+            if let Err(e) = self
+                .dir_states
+                .remember_and_save(&self.curr_absolute_path, state)
+            {
+                self.message = e.to_string();
+            }
+        } else {
+            self.dir_states.remember(&self.curr_absolute_path, state);
+        }
+    }
+
+    /// Applies the cached state of the current directory to the freshly loaded entries,
+    /// falling back to the first entry.
+    fn restore_dir_state(&mut self) {
+        let state = match self.dir_states.get(&self.curr_absolute_path) {
+            Some(state) => state.clone(),
+            None => {
+                self.refresh_displayed_paths();
+                return;
+            }
+        };
+
+        if self.config.remember_filter {
+            self.user_input = state.filter.clone();
+        }
+        self.refresh_displayed_paths();
+
+        if let Some(name) = &state.selected {
+            let absolute = Path::new(&self.curr_absolute_path).join(name);
+            if let Some(index) = self
+                .displayed_paths
+                .iter()
+                .position(|fd| Path::new(&fd.absolute) == absolute)
+            {
+                self.selected_index = index;
+                self.scroll_offset = state.scroll_offset;
+            }
+        }
+    }
+
+    pub fn session(&self) -> session::Session {
+        let position = self.current_position();
+        let mut expanded: Vec<String> = self.tree.expanded.iter().cloned().collect();
+        expanded.sort();
+
+        session::Session {
+            dir: position.dir,
+            selected: position.selected,
+            filter: position.filter,
+            view_mode: self.view_mode.clone(),
+            tree: self.tree.enabled,
+            expanded,
+            hex: self.preview.hex,
+            marks: self
+                .marks
+                .list()
+                .into_iter()
+                .filter(|(name, _)| name.is_ascii_lowercase())
+                .collect(),
+            selection: self.selection.paths(),
+        }
+    }
+
+    pub fn apply_session(&mut self, session: &session::Session) {
+        self.view_mode = session.view_mode.clone();
+        self.tree.enabled = session.tree;
+        self.tree.expanded = session.expanded.iter().cloned().collect();
+        self.preview.hex = session.hex;
+        for (name, target) in &session.marks {
+            if let Err(e) = self.marks.set(*name, target) {
+                self.message = e.to_string();
+            }
+        }
+        // Entries deleted or moved since the session was saved drop out of the selection.
+        for path in &session.selection {
+            if Path::new(path).symlink_metadata().is_ok() {
+                self.selection.insert(path);
+            }
+        }
+
+        let position = jumplist::Position {
+            dir: session.dir.clone(),
+            selected: session.selected.clone(),
+            filter: session.filter.clone(),
+        };
+        self.restore_position(&position);
+    }
+
+    pub fn save_session(&self) -> Result<(), Box<dyn Error>> {
+        self.session().save(&self.session_name)
+    }
+
     fn update_post_move(&mut self, absolute_path: &str) {
+        let from = self.current_position();
+        if self.load_dir(absolute_path) && from.dir != absolute_path {
+            self.jump_list.push(from);
+        }
+    }
+
+    fn load_dir(&mut self, absolute_path: &str) -> bool {
+        self.remember_dir_state();
         let paths = file::get_paths(absolute_path);
         let final_paths = file::generate_file_data(paths);
 
         if let Ok(value) = final_paths {
             self.curr_absolute_path = absolute_path.to_owned();
-            self.inner_paths = value.clone();
-            self.displayed_paths = value;
+            self.parent_paths = file::parent_file_data(absolute_path);
+            self.inner_paths = value;
             self.selected_index = 0;
             self.user_input = "".to_owned();
+            self.restore_dir_state();
             self.message = "".to_owned();
-        } else if let Err(e) = final_paths {
-            self.message = e.to_string();
-            return;
+            if let Err(e) = self.frecency.visit(absolute_path, frecency::now()) {
+                self.message = e.to_string();
+            }
+            true
+        } else {
+            if let Err(e) = final_paths {
+                self.message = e.to_string();
+            }
+            false
         }
     }
 }
@@ -312,18 +2367,60 @@ pub fn initial_app_state() -> Result<AppState, Box<dyn Error>> {
 
     let paths = file::get_paths(cwd);
     let formatted_paths = file::generate_file_data(paths).unwrap();
+    let config = config::Config::load();
+    let dir_states = match dirstate::dir_states_path() {
+        Some(path) if config.persist_dir_state => dirstate::DirStates::load(&path),
+        _ => dirstate::DirStates::default(),
+    };
 
-    return Ok(AppState {
+    let mut app_state = AppState {
         app_mode: AppMode::FileExplorer,
         keybind_mode: KeybindMode::Normal,
+        view_mode: ViewMode::List,
         curr_absolute_path: cwd.to_owned(),
         inner_paths: formatted_paths.clone(),
+        parent_paths: file::parent_file_data(cwd),
         displayed_paths: formatted_paths.clone(),
         selected_index: 0,
+        scroll_offset: 0,
         user_input: "".to_owned(),
         message: "".to_owned(),
         command_mode: false,
-    });
+        preview: preview::Previewer::default(),
+        tree: tree::Tree::default(),
+        finder: finder::Finder::default(),
+        grep: grep::Grep::default(),
+        index: index::IndexSearch::default(),
+        frecency: frecency::database_path()
+            .map(|path| frecency::Frecency::load(&path))
+            .unwrap_or_default(),
+        marks: marks::marks_path()
+            .map(|path| marks::Marks::load(&path))
+            .unwrap_or_default(),
+        pending_key: None,
+        count: None,
+        list_rows: 0,
+        search: search::Search::default(),
+        visual_anchor: 0,
+        register: register::Register::default(),
+        paste: register::Paste::default(),
+        selection: selection::Selection::default(),
+        marked: selection::Selection::default(),
+        deletion: deletion::Review::default(),
+        dialog: ConfirmDialog::default(),
+        trash_items: Vec::new(),
+        journal: journal::Journal::default(),
+        rename: rename::InlineRename::default(),
+        bulk_rename: rename::BulkRename::default(),
+        command_targets: Vec::new(),
+        jump_list: jumplist::JumpList::default(),
+        dir_states,
+        session_name: session::DEFAULT_NAME.to_owned(),
+        config,
+    };
+    app_state.restore_dir_state();
+
+    return Ok(app_state);
 }
 
 #[cfg(test)]
@@ -337,7 +2434,9 @@ mod tests {
                 shortname: name.to_owned(),
                 absolute: "".to_owned(),
                 icon: "".to_owned(),
-                marked_for_deletion: false,
+                is_dir: false,
+                depth: 0,
+                parent: None,
             };
             test_file_data.push(fd);
         }
@@ -354,13 +2453,43 @@ mod tests {
         let mut app_state = AppState {
             app_mode: AppMode::FileExplorer,
             keybind_mode: KeybindMode::Normal,
+            view_mode: ViewMode::List,
             curr_absolute_path: "/Test/test_dir/".to_owned(),
             inner_paths: test_file_data.clone(),
+            parent_paths: Vec::new(),
             displayed_paths: test_file_data,
             selected_index: 0,
+            scroll_offset: 0,
             user_input: "".to_owned(),
             message: "".to_owned(),
             command_mode: false,
+            preview: preview::Previewer::default(),
+            tree: tree::Tree::default(),
+            finder: finder::Finder::default(),
+            grep: grep::Grep::default(),
+            index: index::IndexSearch::default(),
+            frecency: frecency::Frecency::default(),
+            marks: marks::Marks::default(),
+            pending_key: None,
+            count: None,
+            list_rows: 0,
+            search: search::Search::default(),
+            visual_anchor: 0,
+            register: register::Register::default(),
+            paste: register::Paste::default(),
+            selection: selection::Selection::default(),
+            marked: selection::Selection::default(),
+            deletion: deletion::Review::default(),
+            dialog: ConfirmDialog::default(),
+            trash_items: Vec::new(),
+            journal: journal::Journal::default(),
+            rename: rename::InlineRename::default(),
+            bulk_rename: rename::BulkRename::default(),
+            command_targets: Vec::new(),
+            jump_list: jumplist::JumpList::default(),
+            dir_states: dirstate::DirStates::default(),
+            session_name: session::DEFAULT_NAME.to_owned(),
+            config: config::Config::default(),
         };
 
         struct TestCase {
@@ -411,13 +2540,43 @@ mod tests {
         let mut app_state = AppState {
             app_mode: AppMode::FileExplorer,
             keybind_mode: KeybindMode::Normal,
+            view_mode: ViewMode::List,
             curr_absolute_path: "/Test/test_dir/".to_owned(),
             inner_paths: test_file_data.clone(),
+            parent_paths: Vec::new(),
             displayed_paths: test_file_data,
             selected_index: 0,
+            scroll_offset: 0,
             user_input: "test".to_owned(),
             message: "".to_owned(),
             command_mode: false,
+            preview: preview::Previewer::default(),
+            tree: tree::Tree::default(),
+            finder: finder::Finder::default(),
+            grep: grep::Grep::default(),
+            index: index::IndexSearch::default(),
+            frecency: frecency::Frecency::default(),
+            marks: marks::Marks::default(),
+            pending_key: None,
+            count: None,
+            list_rows: 0,
+            search: search::Search::default(),
+            visual_anchor: 0,
+            register: register::Register::default(),
+            paste: register::Paste::default(),
+            selection: selection::Selection::default(),
+            marked: selection::Selection::default(),
+            deletion: deletion::Review::default(),
+            dialog: ConfirmDialog::default(),
+            trash_items: Vec::new(),
+            journal: journal::Journal::default(),
+            rename: rename::InlineRename::default(),
+            bulk_rename: rename::BulkRename::default(),
+            command_targets: Vec::new(),
+            jump_list: jumplist::JumpList::default(),
+            dir_states: dirstate::DirStates::default(),
+            session_name: session::DEFAULT_NAME.to_owned(),
+            config: config::Config::default(),
         };
 
         struct TestCase {
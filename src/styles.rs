@@ -45,3 +45,39 @@ pub const DEFAULT: Color = Color::Rgb {
 
 pub const FILE_ICON: &str = "󰈔 ";
 pub const FOLDER_ICON: &str = " ";
+
+pub const KEYWORD: Color = Color::Rgb {
+    r: 0xbb,
+    g: 0x9a,
+    b: 0xf7,
+};
+
+pub const STRING: Color = Color::Rgb {
+    r: 0x9e,
+    g: 0xce,
+    b: 0x6a,
+};
+
+pub const NUMBER: Color = Color::Rgb {
+    r: 0xff,
+    g: 0x9e,
+    b: 0x64,
+};
+
+pub const TYPE: Color = Color::Rgb {
+    r: 0x2a,
+    g: 0xc3,
+    b: 0xde,
+};
+
+pub const FUNCTION: Color = Color::Rgb {
+    r: 0x7a,
+    g: 0xa2,
+    b: 0xf7,
+};
+
+pub const PROPERTY: Color = Color::Rgb {
+    r: 0x73,
+    g: 0xda,
+    b: 0xca,
+};
@@ -0,0 +1,451 @@
+use crate::config;
+use crate::file;
+use crate::frecency;
+use std::error::Error;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::path::{Path, PathBuf};
+
+/// A trash directory as described by the freedesktop Trash specification: trashed entries are
+/// moved into `files/` and described by a matching `info/<name>.trashinfo`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trash {
+    pub dir: PathBuf,
+}
+
+/// An entry in a trash directory, as described by its `.trashinfo` file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrashedItem {
+    pub trash: Trash,
+    /// Name of the entry in `files/`.
+    pub name: String,
+    pub original: String,
+    pub deleted: String,
+}
+
+/// Escapes everything but unreserved characters and `/`, as `.trashinfo` paths are URL encoded.
+pub fn percent_encode(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+pub fn percent_decode(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = encoded.get(i + 1..i + 3).filter(|_| bytes[i] == b'%');
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The decoded `Path` and the `DeletionDate` of a `.trashinfo` file.
+pub fn parse_info(contents: &str) -> Option<(String, String)> {
+    let mut lines = contents
+        .lines()
+        .skip_while(|line| line.trim() != "[Trash Info]");
+    lines.next()?;
+
+    let (mut path, mut date) = (None, String::new());
+    for line in lines.take_while(|line| !line.starts_with('[')) {
+        match line.split_once('=') {
+            Some(("Path", value)) => path = Some(percent_decode(value)),
+            Some(("DeletionDate", value)) => date = value.to_owned(),
+            _ => {}
+        }
+    }
+    path.map(|path| (path, date))
+}
+
+/// Formats seconds since the epoch as `YYYY-MM-DDThh:mm:ss`. The specification asks for local
+/// time, but without a time zone database UTC is the best the standard library offers.
+pub fn format_date(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rest = secs % 86400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    )
+}
+
+fn current_uid() -> Result<u32, Box<dyn Error>> {
+    Ok(fs::metadata("/proc/self")?.uid())
+}
+
+/// Device of `path`, or of its closest existing ancestor.
+fn device_of(path: &Path) -> Option<u64> {
+    path.ancestors()
+        .find_map(|ancestor| fs::metadata(ancestor).ok())
+        .map(|metadata| metadata.dev())
+}
+
+/// The top directory of the mount `path` lives on: its highest ancestor on the same device.
+fn mount_point(path: &Path, device: u64) -> PathBuf {
+    let mut top = path.to_owned();
+    for ancestor in path.ancestors().skip(1) {
+        match fs::symlink_metadata(ancestor) {
+            Ok(metadata) if metadata.dev() == device => top = ancestor.to_owned(),
+            _ => break,
+        }
+    }
+    top
+}
+
+impl Trash {
+    pub fn new(dir: &Path) -> Trash {
+        Trash {
+            dir: dir.to_owned(),
+        }
+    }
+
+    /// The trash `path` has to go to: the home trash when it is on the same filesystem,
+    /// otherwise `$topdir/.Trash-$uid` on the filesystem of `path`.
+    pub fn for_path(path: &Path) -> Result<Trash, Box<dyn Error>> {
+        let home = config::trash_dir().ok_or("Could not find the trash directory")?;
+        let device = fs::symlink_metadata(path)?.dev();
+        if device_of(&home) == Some(device) {
+            return Ok(Trash::new(&home));
+        }
+
+        let top = mount_point(path, device);
+        Ok(Trash::new(&top.join(format!(".Trash-{}", current_uid()?))))
+    }
+
+    pub fn files_dir(&self) -> PathBuf {
+        self.dir.join("files")
+    }
+
+    pub fn info_dir(&self) -> PathBuf {
+        self.dir.join("info")
+    }
+
+    /// Everything in the trash that has a readable `.trashinfo` file, most recently deleted
+    /// first. Relative paths are resolved against the directory holding the trash.
+    pub fn items(&self) -> Vec<TrashedItem> {
+        let entries = match fs::read_dir(self.info_dir()) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        let top = self.dir.parent().unwrap_or(Path::new("/"));
+
+        let mut items: Vec<TrashedItem> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let file_name = entry.file_name().to_str()?.to_owned();
+                let name = file_name.strip_suffix(".trashinfo")?.to_owned();
+                let contents = fs::read_to_string(entry.path()).ok()?;
+                let (original, deleted) = parse_info(&contents)?;
+                Some(TrashedItem {
+                    trash: self.clone(),
+                    name,
+                    original: top.join(original).to_string_lossy().into_owned(),
+                    deleted,
+                })
+            })
+            .collect();
+        items.sort_by(|a, b| b.deleted.cmp(&a.deleted).then_with(|| a.name.cmp(&b.name)));
+        items
+    }
+
+    /// Permanently deletes everything in the trash, returning how many entries were removed.
+    pub fn empty(&self) -> Result<usize, Box<dyn Error>> {
+        let items = self.items();
+        for item in &items {
+            item.purge()?;
+        }
+
+        // Entries without an info file cannot be restored, so they go as well.
+        if let Ok(entries) = fs::read_dir(self.files_dir()) {
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                remove_entry(&entry.path())?;
+            }
+        }
+        Ok(items.len())
+    }
+
+    /// Moves `path` into the trash and returns where it ended up. The `.trashinfo` file is
+    /// created first so that its name is reserved, and removed again if the move fails.
+    pub fn put(&self, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true).mode(0o700);
+        builder.create(self.files_dir())?;
+        builder.create(self.info_dir())?;
+
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| format!("Cannot trash {}", path.display()))?;
+        let absolute = path.to_str().ok_or("Path is not valid UTF-8")?;
+        let contents = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            percent_encode(absolute),
+            format_date(frecency::now())
+        );
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let candidate = match attempt {
+                1 => name.to_owned(),
+                n => format!("{}.{}", name, n),
+            };
+            let info = self.info_dir().join(format!("{}.trashinfo", candidate));
+            let mut file = match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info)
+            {
+                Ok(file) => file,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            let target = self.files_dir().join(&candidate);
+            if target.symlink_metadata().is_ok() {
+                let _ = fs::remove_file(&info);
+                continue;
+            }
+            let result = file
+                .write_all(contents.as_bytes())
+                .and_then(|_| fs::rename(path, &target));
+            if let Err(e) = result {
+                let _ = fs::remove_file(&info);
+                return Err(e.into());
+            }
+            return Ok(target);
+        }
+    }
+}
+
+fn remove_entry(path: &Path) -> std::io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+impl TrashedItem {
+    pub fn path(&self) -> PathBuf {
+        self.trash.files_dir().join(&self.name)
+    }
+
+    fn info_path(&self) -> PathBuf {
+        self.trash
+            .info_dir()
+            .join(format!("{}.trashinfo", self.name))
+    }
+
+    /// Moves the entry back to where it was deleted from, recreating missing parent
+    /// directories. When something else took its place, it is restored next to it as
+    /// `name (1).ext` instead. Returns where it ended up.
+    pub fn restore(&self) -> Result<PathBuf, Box<dyn Error>> {
+        let original = Path::new(&self.original);
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let target = file::unique_path(original);
+        fs::rename(self.path(), &target)?;
+        fs::remove_file(self.info_path())?;
+        Ok(target)
+    }
+
+    /// Deletes the entry for good.
+    pub fn purge(&self) -> Result<(), Box<dyn Error>> {
+        remove_entry(&self.path())?;
+        fs::remove_file(self.info_path())?;
+        Ok(())
+    }
+}
+
+/// The home trash and the `.Trash-$uid` directories of every mounted filesystem that has one.
+pub fn trashes() -> Vec<Trash> {
+    let mut trashes: Vec<Trash> = config::trash_dir()
+        .map(|dir| Trash::new(&dir))
+        .into_iter()
+        .collect();
+    let (uid, mounts) = match (current_uid(), fs::read_to_string("/proc/mounts")) {
+        (Ok(uid), Ok(mounts)) => (uid, mounts),
+        _ => return trashes,
+    };
+
+    for line in mounts.lines() {
+        // Spaces in mount points are escaped as `\040`.
+        let mount_point = match line.split(' ').nth(1) {
+            Some(mount_point) => mount_point.replace("\\040", " "),
+            None => continue,
+        };
+        let dir = Path::new(&mount_point).join(format!(".Trash-{}", uid));
+        if dir.is_dir() && !trashes.iter().any(|trash| trash.dir == dir) {
+            trashes.push(Trash::new(&dir));
+        }
+    }
+    trashes
+}
+
+/// Moves an entry that `Trash::put` placed at `trashed` back to `original` and drops its
+/// `.trashinfo` file. Unlike `TrashedItem::restore`, this refuses to rename anything.
+pub fn untrash(trashed: &Path, original: &Path) -> Result<(), Box<dyn Error>> {
+    if trashed.symlink_metadata().is_err() {
+        return Err(format!("{} is no longer in the trash", original.display()).into());
+    }
+    if original.symlink_metadata().is_ok() {
+        return Err(format!("{} already exists", original.display()).into());
+    }
+    if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(trashed, original)?;
+
+    if let (Some(files), Some(name)) = (trashed.parent(), trashed.file_name()) {
+        let info = files
+            .with_file_name("info")
+            .join(format!("{}.trashinfo", name.to_string_lossy()));
+        let _ = fs::remove_file(info);
+    }
+    Ok(())
+}
+
+/// Moves `path` into the trash of its filesystem.
+pub fn trash_path(path: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let path = Path::new(path);
+    Trash::for_path(path)?.put(path)
+}
+
+#[cfg(test)]
+mod trash_tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(
+            percent_encode("/home/a b/ü%.txt"),
+            "/home/a%20b/%C3%BC%25.txt"
+        );
+    }
+
+    #[test]
+    fn test_parse_info() {
+        let info =
+            "[Trash Info]\nPath=/home/a%20b/%C3%BC%25.txt\nDeletionDate=2024-01-02T03:04:05\n";
+        assert_eq!(
+            parse_info(info),
+            Some((
+                "/home/a b/ü%.txt".to_owned(),
+                "2024-01-02T03:04:05".to_owned()
+            ))
+        );
+        assert_eq!(parse_info("Path=/missing/header"), None);
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01T00:00:00");
+        assert_eq!(format_date(951782400 + 3661), "2000-02-29T01:01:01");
+        assert_eq!(format_date(1790000000), "2026-09-21T14:13:20");
+    }
+
+    #[test]
+    fn test_put() {
+        let root = std::env::temp_dir().join(format!("slingshot_trash_{}", std::process::id()));
+        let trash = Trash::new(&root.join("Trash"));
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("b")).unwrap();
+        fs::write(root.join("a/note.txt"), "a").unwrap();
+        fs::write(root.join("b/note.txt"), "b").unwrap();
+
+        let first = trash.put(&root.join("a/note.txt")).unwrap();
+        let second = trash.put(&root.join("b/note.txt")).unwrap();
+        assert_eq!(first, trash.files_dir().join("note.txt"));
+        assert_eq!(second, trash.files_dir().join("note.txt.2"));
+        assert!(!root.join("a/note.txt").exists());
+        assert_eq!(fs::read_to_string(second).unwrap(), "b");
+
+        let info = fs::read_to_string(trash.info_dir().join("note.txt.2.trashinfo")).unwrap();
+        let expected = format!("Path={}", root.join("b/note.txt").display());
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.lines().any(|line| line == expected));
+
+        assert!(trash.put(&root.join("missing")).is_err());
+        assert!(!trash.info_dir().join("missing.trashinfo").exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_restore_and_empty() {
+        let root = std::env::temp_dir().join(format!("slingshot_restore_{}", std::process::id()));
+        let trash = Trash::new(&root.join("Trash"));
+        fs::create_dir_all(root.join("dir/sub")).unwrap();
+        fs::write(root.join("dir/sub/a.txt"), "old").unwrap();
+        fs::write(root.join("dir/b.txt"), "b").unwrap();
+
+        trash.put(&root.join("dir/sub/a.txt")).unwrap();
+        trash.put(&root.join("dir/b.txt")).unwrap();
+        let items = trash.items();
+        assert_eq!(items.len(), 2);
+        let a = items.iter().find(|item| item.name == "a.txt").unwrap();
+        assert_eq!(a.original, root.join("dir/sub/a.txt").to_str().unwrap());
+
+        // The parent is gone and the name is taken once it is recreated.
+        fs::remove_dir_all(root.join("dir/sub")).unwrap();
+        let restored = a.restore().unwrap();
+        assert_eq!(restored, root.join("dir/sub/a.txt"));
+        assert_eq!(fs::read_to_string(&restored).unwrap(), "old");
+
+        trash.put(&restored).unwrap();
+        fs::write(&restored, "new").unwrap();
+        let a = trash
+            .items()
+            .into_iter()
+            .find(|item| item.name == "a.txt")
+            .unwrap();
+        assert_eq!(a.restore().unwrap(), root.join("dir/sub/a (1).txt"));
+        assert_eq!(fs::read_to_string(&restored).unwrap(), "new");
+
+        fs::write(trash.files_dir().join("orphan"), "").unwrap();
+        assert_eq!(trash.empty().unwrap(), 1);
+        assert!(trash.items().is_empty());
+        assert_eq!(fs::read_dir(trash.files_dir()).unwrap().count(), 0);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+use crate::file::{self, FileData};
+use std::collections::HashSet;
+use std::fs;
+
+#[derive(Clone, Debug, Default)]
+pub struct Tree {
+    pub enabled: bool,
+    pub expanded: HashSet<String>,
+}
+
+impl Tree {
+    pub fn is_expanded(&self, path: &str) -> bool {
+        self.expanded.contains(path)
+    }
+
+    pub fn expand(&mut self, path: &str) {
+        self.expanded.insert(path.to_owned());
+    }
+
+    /// Collapses `path` along with every expanded directory below it.
+    pub fn collapse(&mut self, path: &str) {
+        let prefix = format!("{}/", path);
+        self.expanded
+            .retain(|expanded| expanded != path && !expanded.starts_with(&prefix));
+    }
+}
+
+fn children_of(node: &FileData) -> Vec<FileData> {
+    let paths = match fs::read_dir(&node.absolute) {
+        Ok(paths) => paths,
+        Err(_) => return Vec::new(),
+    };
+
+    file::generate_file_data(paths)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|child| FileData {
+            depth: node.depth + 1,
+            parent: Some(node.absolute.clone()),
+            ..child
+        })
+        .collect()
+}
+
+/// Flattens `roots` into display order, inserting the children of every expanded directory
+/// right after it.
+pub fn flatten(roots: &[FileData], tree: &Tree) -> Vec<FileData> {
+    let mut output = Vec::new();
+    let mut stack: Vec<FileData> = roots.iter().rev().cloned().collect();
+
+    while let Some(node) = stack.pop() {
+        if node.is_dir && tree.is_expanded(&node.absolute) {
+            stack.extend(children_of(&node).into_iter().rev());
+        }
+        output.push(node);
+    }
+    output
+}
+
+/// Keeps the nodes whose name contains `search_term`, along with their ancestors so that the
+/// matches stay in context.
+pub fn filter(nodes: &[FileData], search_term: &str) -> Vec<FileData> {
+    let search_term = search_term.to_lowercase();
+    let mut keep = vec![false; nodes.len()];
+    let mut needed: HashSet<&str> = HashSet::new();
+
+    for (i, node) in nodes.iter().enumerate().rev() {
+        if needed.contains(node.absolute.as_str())
+            || node.shortname.to_lowercase().contains(&search_term)
+        {
+            keep[i] = true;
+            if let Some(parent) = &node.parent {
+                needed.insert(parent);
+            }
+        }
+    }
+
+    nodes
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(node, _)| node.clone())
+        .collect()
+}
+
+/// Indentation guides for every node, e.g. `│ ├ ` for a node two levels deep that has
+/// siblings below it.
+pub fn guides(nodes: &[FileData]) -> Vec<String> {
+    let mut output = vec![String::new(); nodes.len()];
+    let mut open: Vec<bool> = Vec::new();
+
+    for (i, node) in nodes.iter().enumerate().rev() {
+        let depth = node.depth;
+        open.resize(depth + 1, false);
+
+        if depth > 0 {
+            let mut guide = String::new();
+            for is_open in &open[1..depth] {
+                guide.push_str(if *is_open { "│ " } else { "  " });
+            }
+            guide.push_str(if open[depth] { "├ " } else { "└ " });
+            output[i] = guide;
+        }
+        open[depth] = true;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tree_tests {
+    use super::*;
+
+    fn node(name: &str, depth: usize, parent: Option<&str>) -> FileData {
+        FileData {
+            shortname: name.to_owned(),
+            absolute: match parent {
+                Some(parent) => format!("{}/{}", parent, name),
+                None => format!("/root/{}", name),
+            },
+            icon: "".to_owned(),
+            is_dir: false,
+            depth,
+            parent: parent.map(|p| p.to_owned()),
+        }
+    }
+
+    fn sample_tree() -> Vec<FileData> {
+        vec![
+            node("src", 0, None),
+            node("main.rs", 1, Some("/root/src")),
+            node("ui", 1, Some("/root/src")),
+            node("view.rs", 2, Some("/root/src/ui")),
+            node("file.rs", 1, Some("/root/src")),
+            node("Cargo.toml", 0, None),
+        ]
+    }
+
+    #[test]
+    fn test_flatten() {
+        let dir = format!("{}/tests", env!("CARGO_MANIFEST_DIR"));
+        let roots = file::generate_file_data(fs::read_dir(&dir).unwrap()).unwrap();
+        let mut tree = Tree::default();
+
+        assert_eq!(flatten(&roots, &tree).len(), roots.len());
+
+        let dir1 = format!("{}/dir1", dir);
+        tree.expand(&dir1);
+        let flattened = flatten(&roots, &tree);
+        assert_eq!(flattened.len(), roots.len() + 3);
+
+        let position = flattened.iter().position(|fd| fd.absolute == dir1).unwrap();
+        for child in &flattened[position + 1..position + 4] {
+            assert_eq!(child.depth, 1);
+            assert_eq!(child.parent, Some(dir1.clone()));
+        }
+
+        tree.collapse(&dir1);
+        assert_eq!(flatten(&roots, &tree).len(), roots.len());
+    }
+
+    #[test]
+    fn test_filter() {
+        let filtered: Vec<String> = filter(&sample_tree(), "view")
+            .into_iter()
+            .map(|fd| fd.shortname)
+            .collect();
+        assert_eq!(filtered, vec!["src", "ui", "view.rs"]);
+
+        let filtered: Vec<String> = filter(&sample_tree(), "toml")
+            .into_iter()
+            .map(|fd| fd.shortname)
+            .collect();
+        assert_eq!(filtered, vec!["Cargo.toml"]);
+    }
+
+    #[test]
+    fn test_guides() {
+        assert_eq!(
+            guides(&sample_tree()),
+            vec!["", "├ ", "├ ", "│ └ ", "└ ", ""]
+        );
+    }
+}